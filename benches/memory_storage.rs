@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use schlussel::session::{MemoryStorage, SessionStorage, Token};
+use std::sync::Arc;
+use std::thread;
+
+fn token(access_token: &str) -> Token {
+    Token {
+        access_token: access_token.to_string(),
+        refresh_token: Some("refresh".to_string()),
+        token_type: "Bearer".to_string(),
+        expires_in: Some(3600),
+        expires_at: None,
+        scope: None,
+        token_endpoint: None,
+    }
+}
+
+fn bench_memory_storage(c: &mut Criterion) {
+    let storage = Arc::new(MemoryStorage::new());
+    storage.save_token("example.com:user", token("0")).unwrap();
+
+    c.bench_function("memory_storage_get_token", |b| {
+        b.iter(|| storage.get_token("example.com:user").unwrap())
+    });
+
+    c.bench_function("memory_storage_update_token", |b| {
+        b.iter(|| {
+            storage
+                .update_token("example.com:user", &mut |token| token)
+                .unwrap()
+        })
+    });
+
+    c.bench_function("memory_storage_concurrent_update_token", |b| {
+        b.iter(|| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let storage = storage.clone();
+                    thread::spawn(move || {
+                        for _ in 0..100 {
+                            storage
+                                .update_token("example.com:user", &mut |token| token)
+                                .unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_memory_storage);
+criterion_main!(benches);
@@ -18,14 +18,13 @@ fn main() {
     );
 
     // Configure OAuth
-    let config = OAuthConfig {
-        client_id: "example-client".to_string(),
-        authorization_endpoint: "https://example.com/oauth/authorize".to_string(),
-        token_endpoint: "https://example.com/oauth/token".to_string(),
-        redirect_uri: "http://localhost:8080/callback".to_string(),
-        scope: Some("read write".to_string()),
-        device_authorization_endpoint: None,
-    };
+    let config = OAuthConfig::new(
+        "example-client",
+        "https://example.com/oauth/authorize",
+        "https://example.com/oauth/token",
+        "http://localhost:8080/callback",
+    )
+    .with_scope("read write");
 
     let client = Arc::new(OAuthClient::new(config, storage.clone()));
     let refresher = TokenRefresher::new(client.clone());
@@ -56,7 +55,8 @@ fn demonstrate_valid_token(
         token_type: "Bearer".to_string(),
         expires_in: Some(3600),
         expires_at: Some(now + 3600),
-        scope: Some("read write".to_string()),
+        scope: Some("read write".into()),
+        token_endpoint: None,
     };
 
     client.save_token("example.com:scenario1", token).unwrap();
@@ -98,7 +98,8 @@ fn demonstrate_nearly_expired_token(
         token_type: "Bearer".to_string(),
         expires_in: Some(3600),      // Originally 1 hour
         expires_at: Some(now + 360), // Only 360 seconds (6 minutes) remaining
-        scope: Some("read write".to_string()),
+        scope: Some("read write".into()),
+        token_endpoint: None,
     };
 
     client.save_token("example.com:scenario2", token).unwrap();
@@ -149,7 +150,8 @@ fn demonstrate_expired_token(
         token_type: "Bearer".to_string(),
         expires_in: Some(3600),
         expires_at: Some(now - 100), // Expired 100 seconds ago
-        scope: Some("read write".to_string()),
+        scope: Some("read write".into()),
+        token_endpoint: None,
     };
 
     client.save_token("example.com:scenario3", token).unwrap();
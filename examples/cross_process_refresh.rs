@@ -25,14 +25,13 @@ fn main() {
     );
 
     // Configure OAuth
-    let config = OAuthConfig {
-        client_id: "test-client".to_string(),
-        authorization_endpoint: "https://example.com/oauth/authorize".to_string(),
-        token_endpoint: "https://example.com/oauth/token".to_string(),
-        redirect_uri: "http://localhost:8080/callback".to_string(),
-        scope: Some("read write".to_string()),
-        device_authorization_endpoint: None,
-    };
+    let config = OAuthConfig::new(
+        "test-client",
+        "https://example.com/oauth/authorize",
+        "https://example.com/oauth/token",
+        "http://localhost:8080/callback",
+    )
+    .with_scope("read write");
 
     let client = Arc::new(OAuthClient::new(config, storage.clone()));
 
@@ -57,7 +56,8 @@ fn main() {
                 token_type: "Bearer".to_string(),
                 expires_in: Some(5),
                 expires_at: Some(now + 5), // Expires in 5 seconds
-                scope: Some("read write".to_string()),
+                scope: Some("read write".into()),
+                token_endpoint: None,
             };
 
             client.save_token(token_key, token.clone()).unwrap();
@@ -75,7 +75,7 @@ fn demo_api_request(client: &OAuthClient<FileStorage>) {
 
     match http_client
         .get("https://api.github.com/user")
-        .header("Authorization", format!("Bearer {}", token.access_token))
+        .header("Authorization", token.authorization_header())
         .header("User-Agent", "schlussel-example")
         .send()
     {
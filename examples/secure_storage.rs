@@ -48,7 +48,8 @@ fn main() {
         token_type: "Bearer".to_string(),
         expires_in: Some(3600),
         expires_at: Some(now + 3600),
-        scope: Some("repo user".to_string()),
+        scope: Some("repo user".into()),
+        token_endpoint: None,
     };
 
     println!("=== Saving Token to Secure Storage ===");
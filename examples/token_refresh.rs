@@ -15,14 +15,13 @@ fn main() {
     let storage = Arc::new(MemoryStorage::new());
 
     // Create a mock OAuth config (not used for refresh in this example)
-    let config = OAuthConfig {
-        client_id: "example-client".to_string(),
-        authorization_endpoint: "https://example.com/oauth/authorize".to_string(),
-        token_endpoint: "https://example.com/oauth/token".to_string(),
-        redirect_uri: "http://127.0.0.1:8080/callback".to_string(),
-        scope: Some("read write".to_string()),
-        device_authorization_endpoint: None,
-    };
+    let config = OAuthConfig::new(
+        "example-client",
+        "https://example.com/oauth/authorize",
+        "https://example.com/oauth/token",
+        "http://127.0.0.1:8080/callback",
+    )
+    .with_scope("read write");
 
     let client = Arc::new(OAuthClient::new(config, storage.clone()));
 
@@ -38,7 +37,8 @@ fn main() {
         token_type: "Bearer".to_string(),
         expires_in: Some(3600),
         expires_at: Some(now + 10), // Expires in 10 seconds
-        scope: Some("read write".to_string()),
+        scope: Some("read write".into()),
+        token_endpoint: None,
     };
 
     // Save the token
@@ -58,18 +58,54 @@ SchlusselError schlussel_save_token(
     SchlusselToken* token
 );
 
+/// Get a stored token, refreshing it first if it is expired
+///
+/// Blocks while the refresh request runs; call it off the main thread.
+/// Concurrent calls for the same key perform a single refresh.
+///
+/// @param client The OAuth client
+/// @param key The token key (e.g., "github.com:user")
+/// @return Pointer to token (must be freed with schlussel_token_free), or NULL if
+///         no token is stored under the key or the refresh failed
+SchlusselToken* schlussel_get_valid_token(SchlusselClient* client, const char* key);
+
 /// Get the access token string
 ///
 /// @param token The token
 /// @return Newly allocated string (must be freed with schlussel_string_free), or NULL on error
 char* schlussel_token_get_access_token(SchlusselToken* token);
 
+/// Get the refresh token string
+///
+/// @param token The token
+/// @return Newly allocated string (must be freed with schlussel_string_free), or NULL if there is no refresh token
+char* schlussel_token_get_refresh_token(SchlusselToken* token);
+
+/// Get the granted scopes (space-separated)
+///
+/// @param token The token
+/// @return Newly allocated string (must be freed with schlussel_string_free), or NULL if there is no scope
+char* schlussel_token_get_scope(SchlusselToken* token);
+
+/// Get the expiration time in seconds since the Unix epoch
+///
+/// @param token The token
+/// @return Expiration timestamp, or -1 if the token doesn't expire
+int64_t schlussel_token_get_expires_at(SchlusselToken* token);
+
 /// Check if token is expired
 ///
 /// @param token The token
 /// @return 1 if expired, 0 if not expired
 int32_t schlussel_token_is_expired(SchlusselToken* token);
 
+/// Check if token is expired or will expire within a number of seconds
+///
+/// @param token The token
+/// @param seconds Margin before the expiration time
+/// @return 1 if expired or expiring within the margin, 0 otherwise
+int32_t schlussel_token_is_expired_within(SchlusselToken* token, uint64_t seconds);
+
 /// Free a string allocated by schlussel
 ///
 /// @param s The string to free
@@ -0,0 +1,239 @@
+/// Async storage for sessions and tokens (requires the `async` feature)
+use crate::session::{Session, SessionStorage, StorageError, Token};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Async counterpart of `SessionStorage`
+///
+/// Lets async applications use storage without blocking the executor on file
+/// or keyring IO. Wrap an existing `SessionStorage` in [`BlockingStorage`] to
+/// run it on tokio's blocking thread pool, or use [`AsyncMemoryStorage`].
+pub trait AsyncSessionStorage: Send + Sync {
+    /// Save a session
+    fn save_session(
+        &self,
+        state: &str,
+        session: Session,
+    ) -> impl Future<Output = Result<(), StorageError>> + Send;
+
+    /// Get a session by state
+    fn get_session(
+        &self,
+        state: &str,
+    ) -> impl Future<Output = Result<Option<Session>, StorageError>> + Send;
+
+    /// Delete a session
+    fn delete_session(&self, state: &str) -> impl Future<Output = Result<(), StorageError>> + Send;
+
+    /// Save a token
+    fn save_token(
+        &self,
+        key: &str,
+        token: Token,
+    ) -> impl Future<Output = Result<(), StorageError>> + Send;
+
+    /// Get a token by key
+    fn get_token(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<Token>, StorageError>> + Send;
+
+    /// Delete a token
+    fn delete_token(&self, key: &str) -> impl Future<Output = Result<(), StorageError>> + Send;
+}
+
+/// Runs a blocking `SessionStorage` on tokio's blocking thread pool
+///
+/// Each call moves to `tokio::task::spawn_blocking`, so `FileStorage` and
+/// `SecureStorage` can be used from async code. Must be called within a tokio
+/// runtime.
+///
+/// # Example
+///
+/// ```
+/// use schlussel::async_storage::{AsyncSessionStorage, BlockingStorage};
+/// use schlussel::session::MemoryStorage;
+/// use std::sync::Arc;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let storage = BlockingStorage::new(Arc::new(MemoryStorage::new()));
+/// assert_eq!(storage.get_token("example.com:user").await.unwrap(), None);
+/// # });
+/// ```
+pub struct BlockingStorage<S: SessionStorage + ?Sized> {
+    inner: Arc<S>,
+}
+
+impl<S: SessionStorage + ?Sized + 'static> BlockingStorage<S> {
+    /// Wrap `storage`; the same `Arc` can still be used synchronously
+    pub fn new(storage: Arc<S>) -> Self {
+        Self { inner: storage }
+    }
+
+    /// The wrapped storage
+    pub fn inner(&self) -> &Arc<S> {
+        &self.inner
+    }
+
+    /// Run `f` against the storage on the blocking thread pool
+    async fn run<T, F>(&self, f: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&S) -> Result<T, StorageError> + Send + 'static,
+    {
+        let storage = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || f(&storage))
+            .await
+            .map_err(|e| StorageError::Other(format!("Storage task failed: {}", e)))?
+    }
+}
+
+impl<S: SessionStorage + ?Sized + 'static> AsyncSessionStorage for BlockingStorage<S> {
+    async fn save_session(&self, state: &str, session: Session) -> Result<(), StorageError> {
+        let state = state.to_string();
+        self.run(move |storage| storage.save_session(&state, session))
+            .await
+    }
+
+    async fn get_session(&self, state: &str) -> Result<Option<Session>, StorageError> {
+        let state = state.to_string();
+        self.run(move |storage| storage.get_session(&state)).await
+    }
+
+    async fn delete_session(&self, state: &str) -> Result<(), StorageError> {
+        let state = state.to_string();
+        self.run(move |storage| storage.delete_session(&state))
+            .await
+    }
+
+    async fn save_token(&self, key: &str, token: Token) -> Result<(), StorageError> {
+        let key = key.to_string();
+        self.run(move |storage| storage.save_token(&key, token))
+            .await
+    }
+
+    async fn get_token(&self, key: &str) -> Result<Option<Token>, StorageError> {
+        let key = key.to_string();
+        self.run(move |storage| storage.get_token(&key)).await
+    }
+
+    async fn delete_token(&self, key: &str) -> Result<(), StorageError> {
+        let key = key.to_string();
+        self.run(move |storage| storage.delete_token(&key)).await
+    }
+}
+
+/// In-memory async storage (tokens are lost when the process exits)
+///
+/// Uses tokio's `RwLock`, so concurrent tasks wait without blocking a thread.
+#[derive(Default)]
+pub struct AsyncMemoryStorage {
+    sessions: RwLock<HashMap<String, Session>>,
+    tokens: RwLock<HashMap<String, Token>>,
+}
+
+impl AsyncMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AsyncSessionStorage for AsyncMemoryStorage {
+    async fn save_session(&self, state: &str, session: Session) -> Result<(), StorageError> {
+        self.sessions
+            .write()
+            .await
+            .insert(state.to_string(), session);
+        Ok(())
+    }
+
+    async fn get_session(&self, state: &str) -> Result<Option<Session>, StorageError> {
+        Ok(self.sessions.read().await.get(state).cloned())
+    }
+
+    async fn delete_session(&self, state: &str) -> Result<(), StorageError> {
+        self.sessions.write().await.remove(state);
+        Ok(())
+    }
+
+    async fn save_token(&self, key: &str, token: Token) -> Result<(), StorageError> {
+        self.tokens.write().await.insert(key.to_string(), token);
+        Ok(())
+    }
+
+    async fn get_token(&self, key: &str) -> Result<Option<Token>, StorageError> {
+        Ok(self.tokens.read().await.get(key).cloned())
+    }
+
+    async fn delete_token(&self, key: &str) -> Result<(), StorageError> {
+        self.tokens.write().await.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::MemoryStorage;
+
+    fn token(access_token: &str) -> Token {
+        Token {
+            access_token: access_token.to_string(),
+            refresh_token: None,
+            token_type: "Bearer".to_string(),
+            expires_in: None,
+            expires_at: None,
+            scope: None,
+            token_endpoint: None,
+        }
+    }
+
+    /// Exercise an async storage the way an async client would
+    async fn round_trip(storage: &impl AsyncSessionStorage) {
+        let session = Session::new("state".to_string(), "verifier".to_string());
+        storage
+            .save_session("state", session.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_session("state")
+                .await
+                .unwrap()
+                .unwrap()
+                .code_verifier,
+            "verifier"
+        );
+        storage.delete_session("state").await.unwrap();
+        assert!(storage.get_session("state").await.unwrap().is_none());
+
+        storage
+            .save_token("example.com:user", token("abc"))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_token("example.com:user").await.unwrap(),
+            Some(token("abc"))
+        );
+        storage.delete_token("example.com:user").await.unwrap();
+        assert_eq!(storage.get_token("example.com:user").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_storage_adapter() {
+        let memory = Arc::new(MemoryStorage::new());
+        let storage = BlockingStorage::new(memory.clone());
+        round_trip(&storage).await;
+
+        // Writes land in the wrapped storage
+        storage.save_token("key", token("shared")).await.unwrap();
+        assert_eq!(memory.get_token("key").unwrap(), Some(token("shared")));
+    }
+
+    #[tokio::test]
+    async fn test_async_memory_storage() {
+        round_trip(&AsyncMemoryStorage::new()).await;
+    }
+}
@@ -1,46 +1,245 @@
 /// Local HTTP server for OAuth callbacks
 use crate::error::{OAuthError, Result};
-use std::io::{BufRead, BufReader, Write};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::time::Duration;
 
-/// Callback result containing authorization code and state
+/// Outcome of an OAuth redirect to the callback
+///
+/// Provider-side errors (RFC 6749 Section 4.1.2.1) arrive as the `Error`
+/// variant so the caller can show its own message or keep waiting on the same
+/// server; `Err` is reserved for transport failures and timeouts.
 #[derive(Debug, Clone)]
-pub struct CallbackResult {
-    pub code: String,
-    pub state: String,
+pub enum CallbackResult {
+    /// The user authorized the request
+    Success {
+        code: String,
+        state: String,
+        /// All query parameters of the redirect, URL-decoded
+        ///
+        /// Includes `code` and `state` as well as anything else the provider
+        /// sent, e.g. the granted `scope` or Google's `authuser`.
+        raw_params: HashMap<String, String>,
+    },
+    /// The provider redirected with an `error` parameter
+    Error {
+        error: String,
+        description: Option<String>,
+        error_uri: Option<String>,
+    },
+}
+
+impl CallbackResult {
+    /// Get the authorization code and state, mapping provider errors to `OAuthError`
+    ///
+    /// `access_denied` becomes `AuthorizationDenied`, the errors a `prompt=none`
+    /// request returns when it needs the user become `InteractionRequired`, and
+    /// any other error becomes `OAuthErrorResponse`.
+    pub fn into_code_and_state(self) -> Result<(String, String)> {
+        match self {
+            CallbackResult::Success { code, state, .. } => Ok((code, state)),
+            // The user explicitly declined the request
+            CallbackResult::Error { error, .. } if error == "access_denied" => {
+                Err(OAuthError::AuthorizationDenied)
+            }
+            // A `prompt=none` request needs the user after all
+            CallbackResult::Error {
+                error, description, ..
+            } if INTERACTION_REQUIRED_ERRORS.contains(&error.as_str()) => {
+                Err(OAuthError::InteractionRequired { error, description })
+            }
+            CallbackResult::Error {
+                error,
+                description,
+                error_uri,
+            } => Err(OAuthError::OAuthErrorResponse {
+                error,
+                description,
+                uri: error_uri,
+            }),
+        }
+    }
+}
+
+/// Errors a `prompt=none` authorization returns when it needs the user (OIDC Core 3.1.2.6)
+const INTERACTION_REQUIRED_ERRORS: &[&str] = &[
+    "login_required",
+    "interaction_required",
+    "consent_required",
+    "account_selection_required",
+];
+
+/// Default maximum size of the HTTP request line accepted by the callback server
+pub const DEFAULT_MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Default path the callback server listens on
+pub const DEFAULT_CALLBACK_PATH: &str = "/callback";
+
+/// Delivers the authorization code from the redirect back to the client
+///
+/// `CallbackServer` is the default, loopback HTTP implementation. Implement
+/// this trait to receive the redirect some other way, e.g. through a custom
+/// URL scheme handler (`myapp://callback`) in a sandboxed app, and pass it to
+/// `OAuthClient::authorize_with_receiver`.
+pub trait CodeReceiver {
+    /// Redirect URI to send in the authorization request
+    fn redirect_uri(&self) -> String;
+
+    /// Block until the redirect arrives or `timeout` elapses
+    fn wait(&self, timeout: Duration) -> Result<CallbackResult>;
 }
 
 /// Local callback server for OAuth redirect
 pub struct CallbackServer {
-    listener: TcpListener,
+    listener: Listener,
     port: u16,
+    max_request_line: usize,
+    path: String,
 }
 
 impl CallbackServer {
     /// Create a new callback server on a random available port
     pub fn new() -> Result<Self> {
-        let listener = TcpListener::bind("127.0.0.1:0")?;
+        Self::with_port(0)
+    }
+
+    /// Create a new callback server on a specific port
+    ///
+    /// Use this when the provider requires the exact redirect port to be
+    /// registered. Port `0` picks a random available port.
+    pub fn with_port(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
         let port = listener.local_addr()?.port();
 
-        // Set non-blocking with timeout
-        listener.set_nonblocking(false)?;
+        // Non-blocking accept so waiting can honor its timeout
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener: Listener::Tcp(listener),
+            port,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            path: DEFAULT_CALLBACK_PATH.to_string(),
+        })
+    }
+
+    /// Create a callback server listening on a Unix domain socket
+    ///
+    /// For sandboxed environments where binding a loopback TCP port isn't
+    /// allowed, and for local IdP bridges that forward the redirect to a socket.
+    /// The socket speaks the same HTTP callback format, `redirect_uri()` uses the
+    /// `http+unix://` scheme with the percent-encoded socket path as host, and
+    /// `port()` is `0`. The socket file is removed when the server is dropped.
+    #[cfg(unix)]
+    pub fn unix(path: PathBuf) -> Result<Self> {
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener: Listener::Unix(listener, path),
+            port: 0,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            path: DEFAULT_CALLBACK_PATH.to_string(),
+        })
+    }
+
+    /// Set the path the callback is expected on
+    ///
+    /// Some providers validate the exact registered redirect path (e.g.
+    /// `/oauth/done`). The path is used in `redirect_uri()` and only requests to
+    /// it are accepted. Defaults to [`DEFAULT_CALLBACK_PATH`] (`/callback`).
+    ///
+    /// Returns `InvalidParameter` if the path doesn't start with `/`.
+    pub fn with_path(mut self, path: &str) -> Result<Self> {
+        if !path.starts_with('/') {
+            return Err(OAuthError::InvalidParameter(format!(
+                "callback path must start with '/': {}",
+                path
+            )));
+        }
+        self.path = path.to_string();
+        Ok(self)
+    }
 
-        Ok(Self { listener, port })
+    /// Set the maximum number of bytes read for the HTTP request line
+    ///
+    /// Requests with a longer request line are rejected with
+    /// `431 Request Header Fields Too Large` and the server keeps waiting.
+    /// Defaults to [`DEFAULT_MAX_REQUEST_LINE`] (8 KB).
+    pub fn with_max_request_line(mut self, bytes: usize) -> Self {
+        self.max_request_line = bytes;
+        self
     }
 
     /// Get the redirect URI for this server
     pub fn redirect_uri(&self) -> String {
-        format!("http://127.0.0.1:{}/callback", self.port)
+        match &self.listener {
+            Listener::Tcp(_) => format!("http://127.0.0.1:{}{}", self.port, self.path),
+            #[cfg(unix)]
+            Listener::Unix(_, socket) => format!(
+                "http+unix://{}{}",
+                url::form_urlencoded::byte_serialize(socket.as_os_str().as_bytes())
+                    .collect::<String>(),
+                self.path
+            ),
+        }
     }
 
-    /// Get the port number
+    /// Get the port number (`0` for a Unix domain socket)
     pub fn port(&self) -> u16 {
         self.port
     }
 
     /// Wait for OAuth callback (blocking with timeout)
-    pub fn wait_for_callback(&self, timeout: Duration) -> Result<CallbackResult> {
+    ///
+    /// The server keeps listening after this returns, so it can be reused for
+    /// another authorization. Call `close()` to release the port early.
+    pub fn wait_for_callback(&mut self, timeout: Duration) -> Result<CallbackResult> {
+        self.wait_for_matching(None, timeout)
+    }
+
+    /// Wait for a callback matching any of the expected states
+    ///
+    /// This lets a single server handle several authorization requests at once
+    /// (e.g. logging into multiple accounts): start each flow with its own state
+    /// and redirect all of them to this server. The returned `CallbackResult`
+    /// carries the state that arrived first.
+    ///
+    /// Callbacks with a state that isn't expected are answered with an error page
+    /// and ignored, and the server keeps waiting.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use schlussel::callback::{CallbackResult, CallbackServer};
+    /// use std::time::Duration;
+    ///
+    /// let mut server = CallbackServer::new().unwrap();
+    /// let states = vec!["state-a".to_string(), "state-b".to_string()];
+    /// let result = server.wait_for_any(&states, Duration::from_secs(60)).unwrap();
+    /// if let CallbackResult::Success { state, .. } = result {
+    ///     println!("Callback received for {}", state);
+    /// }
+    /// ```
+    pub fn wait_for_any(
+        &mut self,
+        expected_states: &[String],
+        timeout: Duration,
+    ) -> Result<CallbackResult> {
+        self.wait_for_matching(Some(expected_states), timeout)
+    }
+
+    fn wait_for_matching(
+        &self,
+        expected_states: Option<&[String]>,
+        timeout: Duration,
+    ) -> Result<CallbackResult> {
         // Set timeout for incoming connections
         let deadline = std::time::Instant::now() + timeout;
 
@@ -54,8 +253,8 @@ impl CallbackServer {
 
             // Set a short timeout for accept to allow checking the deadline
             match self.listener.accept() {
-                Ok((stream, _)) => {
-                    if let Some(result) = self.handle_request(stream)? {
+                Ok(stream) => {
+                    if let Some(result) = self.handle_request(stream, expected_states)? {
                         return Ok(result);
                     }
                 }
@@ -68,10 +267,35 @@ impl CallbackServer {
         }
     }
 
-    fn handle_request(&self, stream: TcpStream) -> Result<Option<CallbackResult>> {
-        let mut reader = BufReader::new(stream.try_clone()?);
+    /// Stop accepting connections and release the port
+    ///
+    /// The listener is also released when the server is dropped; this makes it
+    /// explicit, e.g. before re-binding the same fixed port for another login.
+    pub fn close(self) {
+        drop(self);
+    }
+
+    fn handle_request(
+        &self,
+        mut stream: Connection,
+        expected_states: Option<&[String]>,
+    ) -> Result<Option<CallbackResult>> {
         let mut request_line = String::new();
-        reader.read_line(&mut request_line)?;
+
+        // Bound the read so an oversized request line can't grow memory unchecked
+        let limit = self.max_request_line as u64 + 1;
+        let read = BufReader::new(&mut stream)
+            .take(limit)
+            .read_line(&mut request_line)?;
+        if read > self.max_request_line {
+            // Best effort: the client may already have given up on the connection
+            let _ = send_error_response_with_status(
+                stream,
+                "431 Request Header Fields Too Large",
+                "Request too large",
+            );
+            return Ok(None);
+        }
 
         // Parse request line: GET /callback?code=...&state=... HTTP/1.1
         let parts: Vec<&str> = request_line.split_whitespace().collect();
@@ -81,7 +305,7 @@ impl CallbackServer {
         }
 
         let path = parts[1];
-        if !path.starts_with("/callback") {
+        if path.split('?').next() != Some(self.path.as_str()) {
             send_error_response(stream, "Not found")?;
             return Ok(None);
         }
@@ -96,36 +320,173 @@ impl CallbackServer {
 
         let params = parse_query_params(query);
 
-        // Check for error
-        if let Some(error) = params.get("error") {
-            let description = params.get("error_description").map(|s| s.as_str());
-            send_error_response(stream, &format!("Authorization failed: {}", error))?;
-            return Err(OAuthError::OAuthErrorResponse {
-                error: error.clone(),
-                description: description.map(String::from),
-            });
+        // Ignore callbacks that belong to a different authorization request
+        if let (Some(expected), Some(state)) = (expected_states, params.get("state")) {
+            if !expected.contains(state) {
+                send_error_response(stream, "Unknown state parameter")?;
+                return Ok(None);
+            }
         }
 
-        // Extract code and state
-        let code = params
-            .get("code")
-            .ok_or_else(|| OAuthError::MissingField("code".into()))?;
+        let result = callback_result(params)?;
+        match &result {
+            CallbackResult::Error { error, .. } => {
+                send_error_response(stream, &format!("Authorization failed: {}", error))?
+            }
+            CallbackResult::Success { .. } => send_success_response(stream)?,
+        }
+        Ok(Some(result))
+    }
+}
 
-        let state = params
-            .get("state")
-            .ok_or_else(|| OAuthError::MissingField("state".into()))?;
+/// Turn the query parameters of a redirect into a `CallbackResult`
+///
+/// Fails with `MissingField` if a successful redirect lacks `code` or `state`.
+pub(crate) fn callback_result(params: HashMap<String, String>) -> Result<CallbackResult> {
+    // Check for error (RFC 6749 Section 4.1.2.1)
+    if let Some(error) = params.get("error") {
+        return Ok(CallbackResult::Error {
+            error: error.clone(),
+            description: params.get("error_description").cloned(),
+            error_uri: params.get("error_uri").cloned(),
+        });
+    }
 
-        // Send success response
-        send_success_response(stream)?;
+    let code = params
+        .get("code")
+        .cloned()
+        .ok_or_else(|| OAuthError::MissingField("code".into()))?;
 
-        Ok(Some(CallbackResult {
-            code: code.clone(),
-            state: state.clone(),
-        }))
+    let state = params
+        .get("state")
+        .cloned()
+        .ok_or_else(|| OAuthError::MissingField("state".into()))?;
+
+    Ok(CallbackResult::Success {
+        code,
+        state,
+        raw_params: params,
+    })
+}
+
+#[cfg(unix)]
+impl Drop for CallbackServer {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, socket) = &self.listener {
+            // Best effort: a leftover socket file only blocks re-binding the path
+            let _ = std::fs::remove_file(socket);
+        }
+    }
+}
+
+/// Socket a `CallbackServer` accepts connections on
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    /// Accept a connection without blocking, returning it in blocking mode
+    fn accept(&self) -> std::io::Result<Connection> {
+        // Accepted sockets may inherit non-blocking mode on some platforms
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(Connection::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(Connection::Unix(stream))
+            }
+        }
+    }
+}
+
+/// Connection accepted by a `Listener`
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.read(buf),
+        }
     }
 }
 
-fn parse_query_params(query: &str) -> std::collections::HashMap<String, String> {
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl CodeReceiver for CallbackServer {
+    fn redirect_uri(&self) -> String {
+        CallbackServer::redirect_uri(self)
+    }
+
+    fn wait(&self, timeout: Duration) -> Result<CallbackResult> {
+        self.wait_for_matching(None, timeout)
+    }
+}
+
+/// Check whether two redirect URIs refer to the same endpoint
+///
+/// Providers and `CallbackServer` can spell the same redirect differently, e.g.
+/// `http://127.0.0.1:8080/callback/` vs `http://localhost:8080/callback`. This
+/// compares the parsed URIs instead of the strings: the scheme, port (with
+/// default ports filled in), path (ignoring a single trailing slash) and query
+/// must match, and the loopback hosts `127.0.0.1`, `[::1]` and `localhost` are
+/// treated as equivalent. Returns `false` if either URI can't be parsed.
+///
+/// `OAuthClient::exchange_redirect` uses it to check that a redirect belongs to
+/// the flow's redirect URI. The token exchange must still send the exact string
+/// used in the authorization request.
+pub fn redirect_uris_match(a: &str, b: &str) -> bool {
+    let (Ok(a), Ok(b)) = (url::Url::parse(a), url::Url::parse(b)) else {
+        return false;
+    };
+
+    let host = |url: &url::Url| match url.host() {
+        Some(url::Host::Domain("localhost"))
+        | Some(url::Host::Ipv4(std::net::Ipv4Addr::LOCALHOST))
+        | Some(url::Host::Ipv6(std::net::Ipv6Addr::LOCALHOST)) => Some("localhost".to_string()),
+        other => other.map(|host| host.to_string().to_ascii_lowercase()),
+    };
+    let path = |url: &url::Url| {
+        let path = url.path();
+        path.strip_suffix('/').unwrap_or(path).to_string()
+    };
+
+    a.scheme() == b.scheme()
+        && host(&a) == host(&b)
+        && a.port_or_known_default() == b.port_or_known_default()
+        && path(&a) == path(&b)
+        && a.query() == b.query()
+}
+
+pub(crate) fn parse_query_params(query: &str) -> HashMap<String, String> {
     query
         .split('&')
         .filter_map(|pair| {
@@ -140,7 +501,7 @@ fn parse_query_params(query: &str) -> std::collections::HashMap<String, String>
         .collect()
 }
 
-fn send_success_response(mut stream: TcpStream) -> Result<()> {
+fn send_success_response(mut stream: Connection) -> Result<()> {
     let html = r#"<!DOCTYPE html>
 <html>
 <head>
@@ -187,6 +548,11 @@ fn send_success_response(mut stream: TcpStream) -> Result<()> {
         <h1>Authorization Successful!</h1>
         <p>You have successfully authorized the application. You can close this window and return to your terminal.</p>
     </div>
+    <script>
+        // Browsers only honor window.close() for script-opened tabs; the
+        // text above stays visible in the remaining cases.
+        setTimeout(function () { window.close(); }, 1000);
+    </script>
 </body>
 </html>"#;
 
@@ -201,7 +567,15 @@ fn send_success_response(mut stream: TcpStream) -> Result<()> {
     Ok(())
 }
 
-fn send_error_response(mut stream: TcpStream, error: &str) -> Result<()> {
+fn send_error_response(stream: Connection, error: &str) -> Result<()> {
+    send_error_response_with_status(stream, "400 Bad Request", error)
+}
+
+fn send_error_response_with_status(
+    mut stream: Connection,
+    status: &str,
+    error: &str,
+) -> Result<()> {
     let html = format!(
         r#"<!DOCTYPE html>
 <html>
@@ -254,7 +628,8 @@ fn send_error_response(mut stream: TcpStream, error: &str) -> Result<()> {
     );
 
     let response = format!(
-        "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+        status,
         html.len(),
         html
     );
@@ -301,6 +676,90 @@ mod tests {
         assert!(server.redirect_uri().contains("/callback"));
     }
 
+    #[test]
+    fn test_custom_callback_path() {
+        assert!(CallbackServer::new()
+            .unwrap()
+            .with_path("oauth/done")
+            .is_err());
+
+        let mut server = CallbackServer::new()
+            .unwrap()
+            .with_path("/oauth/done")
+            .unwrap();
+        let port = server.port();
+        assert_eq!(
+            server.redirect_uri(),
+            format!("http://127.0.0.1:{}/oauth/done", port)
+        );
+
+        let handle = std::thread::spawn(move || {
+            for request in [
+                "GET /callback?code=code-x&state=state-x HTTP/1.1\r\n\r\n",
+                "GET /oauth/done?code=code-a&state=state-a HTTP/1.1\r\n\r\n",
+            ] {
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                stream.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                let _ = stream.read_to_string(&mut response);
+            }
+        });
+
+        let result = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+
+        // The default path is no longer accepted
+        let (code, state) = result.into_code_and_state().unwrap();
+        assert_eq!(code, "code-a");
+        assert_eq!(state, "state-a");
+    }
+
+    #[test]
+    fn test_callback_result_raw_params() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream
+                .write_all(
+                    b"GET /callback?code=abc&state=xyz&scope=openid%20email&authuser=0 HTTP/1.1\r\n\r\n",
+                )
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+        });
+
+        let result = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+
+        match result {
+            CallbackResult::Success {
+                code,
+                state,
+                raw_params,
+            } => {
+                assert_eq!(code, "abc");
+                assert_eq!(state, "xyz");
+                assert_eq!(raw_params.len(), 4);
+                assert_eq!(raw_params["scope"], "openid email");
+                assert_eq!(raw_params["authuser"], "0");
+                assert_eq!(raw_params["code"], "abc");
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_wait_for_callback_times_out() {
+        let mut server = CallbackServer::new().unwrap();
+
+        let started = std::time::Instant::now();
+        let result = server.wait_for_callback(Duration::from_millis(200));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
     #[test]
     fn test_query_param_parsing() {
         let query = "code=abc123&state=xyz789";
@@ -309,6 +768,282 @@ mod tests {
         assert_eq!(params.get("state"), Some(&"xyz789".to_string()));
     }
 
+    #[test]
+    fn test_callback_server_with_port() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let server = CallbackServer::with_port(port).unwrap();
+        assert_eq!(server.port(), port);
+        assert!(CallbackServer::with_port(port).is_err());
+    }
+
+    #[test]
+    fn test_close_releases_port() {
+        let server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        server.close();
+
+        // The same port can be bound again right away
+        let listener = TcpListener::bind(("127.0.0.1", port));
+        assert!(listener.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_callback() {
+        let socket = std::env::temp_dir().join(format!("schlussel_{}.sock", rand::random::<u32>()));
+        let mut server = CallbackServer::unix(socket.clone()).unwrap();
+        assert_eq!(server.port(), 0);
+        assert!(server.redirect_uri().starts_with("http+unix://%2F"));
+        assert!(server.redirect_uri().ends_with(".sock/callback"));
+
+        let client_socket = socket.clone();
+        let handle = std::thread::spawn(move || {
+            let mut stream = UnixStream::connect(client_socket).unwrap();
+            stream
+                .write_all(b"GET /callback?code=uds-code&state=uds-state HTTP/1.1\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response
+        });
+
+        let result = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        assert!(handle.join().unwrap().starts_with("HTTP/1.1 200 OK"));
+        let (code, state) = result.into_code_and_state().unwrap();
+        assert_eq!(code, "uds-code");
+        assert_eq!(state, "uds-state");
+
+        // Dropping the server removes the socket file
+        server.close();
+        assert!(!socket.exists());
+    }
+
+    #[test]
+    fn test_oversized_request_line_rejected() {
+        let server = CallbackServer::new().unwrap().with_max_request_line(1024);
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let request = format!("GET /callback?code={} HTTP/1.1\r\n\r\n", "a".repeat(4096));
+            // The server may close the connection before the whole request is written
+            let _ = stream.write_all(request.as_bytes());
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response
+        });
+
+        let stream = loop {
+            match server.listener.accept() {
+                Ok(stream) => break stream,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => panic!("accept failed: {}", e),
+            }
+        };
+        let result = server.handle_request(stream, None).unwrap();
+        assert!(result.is_none());
+
+        let response = handle.join().unwrap();
+        if !response.is_empty() {
+            assert!(response.starts_with("HTTP/1.1 431"));
+        }
+    }
+
+    #[test]
+    fn test_wait_for_any_returns_matching_state() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            for request in [
+                "GET /callback?code=code-x&state=unknown HTTP/1.1\r\n\r\n",
+                "GET /callback?code=code-b&state=state-b HTTP/1.1\r\n\r\n",
+            ] {
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                stream.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                let _ = stream.read_to_string(&mut response);
+            }
+        });
+
+        let states = vec!["state-a".to_string(), "state-b".to_string()];
+        let result = server
+            .wait_for_any(&states, Duration::from_secs(5))
+            .unwrap();
+        handle.join().unwrap();
+
+        let (code, state) = result.into_code_and_state().unwrap();
+        assert_eq!(state, "state-b");
+        assert_eq!(code, "code-b");
+    }
+
+    #[test]
+    fn test_access_denied_callback() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream
+                .write_all(
+                    b"GET /callback?error=access_denied&error_description=User%20cancelled&state=xyz HTTP/1.1\r\n\r\n",
+                )
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+        });
+
+        let result = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        assert!(matches!(
+            result.into_code_and_state(),
+            Err(OAuthError::AuthorizationDenied)
+        ));
+    }
+
+    #[test]
+    fn test_error_callback_includes_uri() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream
+                .write_all(
+                    b"GET /callback?error=invalid_scope&error_description=Bad%20scope&error_uri=https%3A%2F%2Fexample.com%2Ferr HTTP/1.1\r\n\r\n",
+                )
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+        });
+
+        let result = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        match result.clone() {
+            CallbackResult::Error {
+                error,
+                description,
+                error_uri,
+            } => {
+                assert_eq!(error, "invalid_scope");
+                assert_eq!(description, Some("Bad scope".to_string()));
+                assert_eq!(error_uri, Some("https://example.com/err".to_string()));
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            result.into_code_and_state(),
+            Err(OAuthError::OAuthErrorResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_server_reusable_after_error_callback() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            for request in [
+                "GET /callback?error=server_error&state=xyz HTTP/1.1\r\n\r\n",
+                "GET /callback?code=retry&state=xyz HTTP/1.1\r\n\r\n",
+            ] {
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                stream.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                let _ = stream.read_to_string(&mut response);
+            }
+        });
+
+        let first = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        assert!(
+            matches!(first, CallbackResult::Error { ref error, .. } if error == "server_error")
+        );
+
+        // The same server keeps listening for the retry
+        let second = server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        let (code, _) = second.into_code_and_state().unwrap();
+        assert_eq!(code, "retry");
+    }
+
+    #[test]
+    fn test_success_response_includes_close_script() {
+        let mut server = CallbackServer::new().unwrap();
+        let port = server.port();
+
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream
+                .write_all(b"GET /callback?code=abc&state=xyz HTTP/1.1\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response
+        });
+
+        server.wait_for_callback(Duration::from_secs(5)).unwrap();
+        let response = handle.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("window.close()"));
+        // Nothing navigates away from the fallback text
+        assert!(!response.contains(r#"http-equiv="refresh""#));
+        assert!(response.contains("You can close this window"));
+    }
+
+    #[test]
+    fn test_redirect_uris_match() {
+        // Trailing slash
+        assert!(redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "http://127.0.0.1:8080/callback/"
+        ));
+        // Loopback host spellings
+        assert!(redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "http://localhost:8080/callback"
+        ));
+        assert!(redirect_uris_match(
+            "http://[::1]:8080/callback",
+            "http://LOCALHOST:8080/callback"
+        ));
+        // Default ports
+        assert!(redirect_uris_match(
+            "https://app.example.com:443/oauth",
+            "https://app.example.com/oauth/"
+        ));
+        assert!(redirect_uris_match("myapp://callback", "myapp://callback"));
+
+        assert!(!redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "http://127.0.0.1:8081/callback"
+        ));
+        assert!(!redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "https://127.0.0.1:8080/callback"
+        ));
+        assert!(!redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "http://127.0.0.1:8080/other"
+        ));
+        assert!(!redirect_uris_match(
+            "https://app.example.com/oauth",
+            "https://evil.example.com/oauth"
+        ));
+        assert!(!redirect_uris_match(
+            "http://127.0.0.1:8080/callback",
+            "http://127.0.0.1:8080/callback//"
+        ));
+        assert!(!redirect_uris_match("not a url", "not a url"));
+    }
+
     #[test]
     fn test_query_param_url_decoding() {
         let query = "code=abc%20123&state=xyz%2F789";
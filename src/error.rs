@@ -10,17 +10,44 @@ pub enum OAuthError {
     JsonError(#[from] serde_json::Error),
 
     #[error("Storage error: {0}")]
-    StorageError(String),
+    StorageError(#[from] crate::session::StorageError),
 
     #[error("Invalid state parameter")]
     InvalidState,
 
+    #[error("Authorization session expired")]
+    SessionExpired,
+
     #[error("Authorization denied by user")]
     AuthorizationDenied,
 
     #[error("Device code expired")]
     DeviceCodeExpired,
 
+    /// The authorization server kept answering with HTTP 429
+    ///
+    /// Returned once the client's rate-limit retries are used up, or right away
+    /// when `Retry-After` asks for a longer wait than the client allows.
+    #[error("Rate limited by the authorization server (retry after {retry_after:?})")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// Silent authorization (`prompt=none`) can't complete without the user
+    ///
+    /// Returned for the OIDC errors `login_required`, `interaction_required`,
+    /// `consent_required` and `account_selection_required`. Fall back to an
+    /// interactive login.
+    #[error("User interaction required: {error}")]
+    InteractionRequired {
+        error: String,
+        description: Option<String>,
+    },
+
+    /// The flow was cancelled through its cancellation flag
+    #[error("Authorization cancelled")]
+    Cancelled,
+
     #[error("Authorization pending")]
     AuthorizationPending,
 
@@ -37,6 +64,8 @@ pub enum OAuthError {
     OAuthErrorResponse {
         error: String,
         description: Option<String>,
+        /// Optional `error_uri` pointing to a human-readable error page
+        uri: Option<String>,
     },
 
     #[error("Token expired")]
@@ -51,8 +80,46 @@ pub enum OAuthError {
     #[error("Missing required field: {0}")]
     MissingField(String),
 
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    /// The `HttpTransport` doesn't implement a request kind, e.g. GET
+    #[error("Not supported: {0}")]
+    Unsupported(String),
+
+    /// An endpoint failed the `OAuthClient::preflight` check
+    #[error("{endpoint} endpoint {url} is unreachable: {reason}")]
+    EndpointUnreachable {
+        endpoint: String,
+        url: String,
+        reason: String,
+    },
+
+    /// A refresh of this token failed recently (possibly in another process)
+    ///
+    /// `TokenRefresher` doesn't retry until `retry_in` has passed, so a
+    /// transient provider failure doesn't turn into a refresh storm.
+    #[error("Token refresh failed recently, retrying in {retry_in:?}: {last_error}")]
+    RefreshBackoff {
+        last_error: String,
+        retry_in: std::time::Duration,
+    },
+
+    /// No directory for cross-process lock files could be created
+    ///
+    /// Fall back to `TokenRefresher::new` for in-process locking only.
+    #[error("Cross-process locking unavailable: {0}")]
+    LockingUnavailable(String),
+
+    /// OS credential manager error from `SecureStorage`
+    ///
+    /// Match on the inner error to tell e.g. `keyring::Error::NoStorageAccess`
+    /// (locked keychain) from `keyring::Error::PlatformFailure`.
+    #[error("Keyring error: {0}")]
+    KeyringError(#[from] keyring::Error),
 }
 
 pub type Result<T> = std::result::Result<T, OAuthError>;
@@ -1,10 +1,11 @@
 /// C FFI for Swift/Objective-C interoperability
-use crate::oauth::{OAuthClient, OAuthConfig};
-use crate::session::{SecureStorage, Token};
+use crate::oauth::{DynOAuthClient, DynTokenRefresher, OAuthClient, OAuthConfig, TokenRefresher};
+use crate::session::{SecureStorage, SessionStorage, Token};
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::ptr;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Opaque pointer to OAuthClient
 pub struct SchlusselClient {
@@ -16,6 +17,28 @@ pub struct SchlusselToken {
     _private: [u8; 0],
 }
 
+/// Client behind a `SchlusselClient` pointer
+///
+/// Keeps one `TokenRefresher` per client so concurrent
+/// `schlussel_get_valid_token` calls share its in-process locking.
+struct ClientHandle {
+    client: Arc<DynOAuthClient>,
+    refresher: DynTokenRefresher,
+}
+
+impl ClientHandle {
+    fn new(client: Arc<DynOAuthClient>, app_name: &str) -> Self {
+        // Fall back to in-process locking if no lock directory is writable
+        let refresher = TokenRefresher::with_file_locking(client.clone(), app_name)
+            .unwrap_or_else(|_| TokenRefresher::new(client.clone()));
+        Self { client, refresher }
+    }
+
+    fn into_raw(self) -> *mut SchlusselClient {
+        Box::into_raw(Box::new(self)) as *mut SchlusselClient
+    }
+}
+
 /// Error code
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -65,7 +88,7 @@ pub unsafe extern "C" fn schlussel_client_new_github(
     };
 
     // Create secure storage
-    let storage = match SecureStorage::new(app_name_str) {
+    let storage: Arc<dyn SessionStorage> = match SecureStorage::new(app_name_str) {
         Ok(s) => Arc::new(s),
         Err(_) => return ptr::null_mut(),
     };
@@ -76,7 +99,7 @@ pub unsafe extern "C" fn schlussel_client_new_github(
     // Create client
     let client = Arc::new(OAuthClient::new(config, storage));
 
-    Box::into_raw(Box::new(client)) as *mut SchlusselClient
+    ClientHandle::new(client, app_name_str).into_raw()
 }
 
 /// Authorize using Device Code Flow
@@ -93,9 +116,9 @@ pub unsafe extern "C" fn schlussel_authorize_device(
         return ptr::null_mut();
     }
 
-    let client_ref = &*(client as *const Arc<OAuthClient<SecureStorage>>);
+    let client_ref = &*(client as *const ClientHandle);
 
-    match client_ref.authorize_device() {
+    match client_ref.client.authorize_device() {
         Ok(token) => Box::into_raw(Box::new(token)) as *mut SchlusselToken,
         Err(_) => ptr::null_mut(),
     }
@@ -118,7 +141,7 @@ pub unsafe extern "C" fn schlussel_save_token(
         return SchlusselError::InvalidParameter;
     }
 
-    let client_ref = &*(client as *const Arc<OAuthClient<SecureStorage>>);
+    let client_ref = &*(client as *const ClientHandle);
     let token_ref = &*(token as *const Token);
 
     let key_str = match CStr::from_ptr(key).to_str() {
@@ -126,12 +149,46 @@ pub unsafe extern "C" fn schlussel_save_token(
         Err(_) => return SchlusselError::InvalidParameter,
     };
 
-    match client_ref.save_token(key_str, token_ref.clone()) {
+    match client_ref.client.save_token(key_str, token_ref.clone()) {
         Ok(_) => SchlusselError::Ok,
         Err(_) => SchlusselError::StorageError,
     }
 }
 
+/// Get a stored token, refreshing it first if it is expired
+///
+/// This blocks the calling thread while the refresh request runs, so call it
+/// off the main thread. Concurrent calls for the same key (also from other
+/// processes of the same app) perform a single refresh.
+///
+/// # Safety
+///
+/// - `client` must be a valid client pointer
+/// - `key` must be a valid null-terminated UTF-8 string
+/// - Returns a token that must be freed with `schlussel_token_free`
+/// - Returns null if no token is stored under `key` or the refresh failed
+#[no_mangle]
+pub unsafe extern "C" fn schlussel_get_valid_token(
+    client: *mut SchlusselClient,
+    key: *const c_char,
+) -> *mut SchlusselToken {
+    if client.is_null() || key.is_null() {
+        return ptr::null_mut();
+    }
+
+    let client_ref = &*(client as *const ClientHandle);
+
+    let key_str = match CStr::from_ptr(key).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    match client_ref.refresher.get_valid_token(key_str) {
+        Ok(token) => Box::into_raw(Box::new(token)) as *mut SchlusselToken,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
 /// Get the access token from a token object
 ///
 /// # Safety
@@ -148,8 +205,73 @@ pub unsafe extern "C" fn schlussel_token_get_access_token(
     }
 
     let token_ref = &*(token as *const Token);
+    to_c_string(&token_ref.access_token)
+}
 
-    match CString::new(token_ref.access_token.clone()) {
+/// Get the refresh token from a token object
+///
+/// # Safety
+///
+/// - `token` must be a valid token pointer
+/// - Returns a newly allocated string that must be freed with `schlussel_string_free`
+/// - Returns null if the token has no refresh token or on error
+#[no_mangle]
+pub unsafe extern "C" fn schlussel_token_get_refresh_token(
+    token: *mut SchlusselToken,
+) -> *mut c_char {
+    if token.is_null() {
+        return ptr::null_mut();
+    }
+
+    let token_ref = &*(token as *const Token);
+    match &token_ref.refresh_token {
+        Some(refresh_token) => to_c_string(refresh_token),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Get the granted scopes (space-separated) from a token object
+///
+/// # Safety
+///
+/// - `token` must be a valid token pointer
+/// - Returns a newly allocated string that must be freed with `schlussel_string_free`
+/// - Returns null if the token has no scope or on error
+#[no_mangle]
+pub unsafe extern "C" fn schlussel_token_get_scope(token: *mut SchlusselToken) -> *mut c_char {
+    if token.is_null() {
+        return ptr::null_mut();
+    }
+
+    let token_ref = &*(token as *const Token);
+    match &token_ref.scope {
+        Some(scope) => to_c_string(&scope.to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Get the expiration time of a token as seconds since the Unix epoch
+///
+/// # Safety
+///
+/// - `token` must be a valid token pointer
+/// - Returns -1 if the token doesn't expire or `token` is null
+#[no_mangle]
+pub unsafe extern "C" fn schlussel_token_get_expires_at(token: *mut SchlusselToken) -> i64 {
+    if token.is_null() {
+        return -1;
+    }
+
+    let token_ref = &*(token as *const Token);
+    token_ref
+        .expires_at
+        .and_then(|expires_at| i64::try_from(expires_at).ok())
+        .unwrap_or(-1)
+}
+
+/// Allocate a C string, returning null if `s` contains a nul byte
+fn to_c_string(s: &str) -> *mut c_char {
+    match CString::new(s) {
         Ok(s) => s.into_raw(),
         Err(_) => ptr::null_mut(),
     }
@@ -175,6 +297,32 @@ pub unsafe extern "C" fn schlussel_token_is_expired(token: *mut SchlusselToken)
     }
 }
 
+/// Check if a token is expired or will expire within `seconds`
+///
+/// Use this to refresh proactively instead of sending a token that expires
+/// mid-request.
+///
+/// # Safety
+///
+/// - `token` must be a valid token pointer
+/// - Returns 1 if expired or expiring within `seconds`, 0 otherwise
+#[no_mangle]
+pub unsafe extern "C" fn schlussel_token_is_expired_within(
+    token: *mut SchlusselToken,
+    seconds: u64,
+) -> i32 {
+    if token.is_null() {
+        return 0;
+    }
+
+    let token_ref = &*(token as *const Token);
+    if token_ref.is_expired_with_leeway(Duration::from_secs(seconds)) {
+        1
+    } else {
+        0
+    }
+}
+
 /// Free a string allocated by schlussel
 ///
 /// # Safety
@@ -210,8 +358,151 @@ pub unsafe extern "C" fn schlussel_token_free(token: *mut SchlusselToken) {
 #[no_mangle]
 pub unsafe extern "C" fn schlussel_client_free(client: *mut SchlusselClient) {
     if !client.is_null() {
-        drop(Box::from_raw(
-            client as *mut Arc<OAuthClient<SecureStorage>>,
-        ));
+        drop(Box::from_raw(client as *mut ClientHandle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::unix_now;
+    use crate::session::MemoryStorage;
+    use crate::testing::MockOAuthServer;
+
+    fn into_ffi(token: Token) -> *mut SchlusselToken {
+        Box::into_raw(Box::new(token)) as *mut SchlusselToken
+    }
+
+    unsafe fn take_string(s: *mut c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let value = CStr::from_ptr(s).to_str().unwrap().to_string();
+        schlussel_string_free(s);
+        Some(value)
+    }
+
+    #[test]
+    fn test_token_getters_with_refresh_token() {
+        let token = into_ffi(Token {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            token_type: "Bearer".to_string(),
+            expires_in: Some(3600),
+            expires_at: Some(1_700_000_000),
+            scope: Some("repo user".into()),
+            token_endpoint: None,
+        });
+
+        unsafe {
+            assert_eq!(
+                take_string(schlussel_token_get_access_token(token)),
+                Some("access".to_string())
+            );
+            assert_eq!(
+                take_string(schlussel_token_get_refresh_token(token)),
+                Some("refresh".to_string())
+            );
+            assert_eq!(
+                take_string(schlussel_token_get_scope(token)),
+                Some("repo user".to_string())
+            );
+            assert_eq!(schlussel_token_get_expires_at(token), 1_700_000_000);
+            schlussel_token_free(token);
+        }
+    }
+
+    #[test]
+    fn test_token_getters_without_optional_fields() {
+        let token = into_ffi(Token {
+            access_token: "access".to_string(),
+            refresh_token: None,
+            token_type: "Bearer".to_string(),
+            expires_in: None,
+            expires_at: None,
+            scope: None,
+            token_endpoint: None,
+        });
+
+        unsafe {
+            assert!(schlussel_token_get_refresh_token(token).is_null());
+            assert!(schlussel_token_get_scope(token).is_null());
+            assert_eq!(schlussel_token_get_expires_at(token), -1);
+            schlussel_token_free(token);
+
+            assert!(schlussel_token_get_refresh_token(ptr::null_mut()).is_null());
+            assert_eq!(schlussel_token_get_expires_at(ptr::null_mut()), -1);
+        }
+    }
+
+    #[test]
+    fn test_token_is_expired_within() {
+        let now = unix_now();
+        let token = into_ffi(Token {
+            access_token: "access".to_string(),
+            refresh_token: None,
+            token_type: "Bearer".to_string(),
+            expires_in: Some(60),
+            expires_at: Some(now + 60),
+            scope: None,
+            token_endpoint: None,
+        });
+
+        unsafe {
+            assert_eq!(schlussel_token_is_expired(token), 0);
+            assert_eq!(schlussel_token_is_expired_within(token, 30), 0);
+            assert_eq!(schlussel_token_is_expired_within(token, 120), 1);
+            schlussel_token_free(token);
+
+            assert_eq!(schlussel_token_is_expired_within(ptr::null_mut(), 120), 0);
+        }
+    }
+
+    #[test]
+    fn test_get_valid_token_refreshes_expired_token() {
+        let server = MockOAuthServer::start().unwrap();
+        let storage: Arc<dyn SessionStorage> = Arc::new(MemoryStorage::new());
+        let client = Arc::new(OAuthClient::new(server.config("test-client"), storage));
+        client
+            .save_token(
+                "github.com:user",
+                Token {
+                    access_token: "stale".to_string(),
+                    refresh_token: Some("refresh".to_string()),
+                    token_type: "Bearer".to_string(),
+                    expires_in: Some(3600),
+                    expires_at: Some(1),
+                    scope: None,
+                    token_endpoint: None,
+                },
+            )
+            .unwrap();
+        let handle = ClientHandle {
+            refresher: TokenRefresher::new(client.clone()),
+            client,
+        }
+        .into_raw();
+
+        unsafe {
+            let key = CString::new("github.com:user").unwrap();
+            let token = schlussel_get_valid_token(handle, key.as_ptr());
+            assert!(!token.is_null());
+            assert_eq!(
+                take_string(schlussel_token_get_access_token(token)),
+                Some("mock_access_token".to_string())
+            );
+            schlussel_token_free(token);
+
+            let missing = CString::new("github.com:other").unwrap();
+            assert!(schlussel_get_valid_token(handle, missing.as_ptr()).is_null());
+            assert!(schlussel_get_valid_token(handle, ptr::null()).is_null());
+
+            schlussel_client_free(handle);
+        }
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].param("grant_type"), Some("refresh_token"));
+        assert_eq!(requests[0].param("refresh_token"), Some("refresh"));
     }
 }
@@ -0,0 +1,326 @@
+/// HTTP transport abstraction for token endpoint requests
+use crate::error::{OAuthError, Result};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Helper to safely drop blocking client in a blocking context
+///
+/// This prevents "Cannot drop a runtime in a context where blocking is not allowed" errors
+/// that occur when dropping reqwest::blocking::Client in async contexts.
+///
+/// **Implementation Note**: We intentionally leak the client using `std::mem::forget` because:
+/// 1. The reqwest blocking client creates an internal tokio runtime
+/// 2. Dropping that runtime in an async context causes panics
+/// 3. For CLI applications, leaking a small HTTP client is acceptable
+/// 4. The OS will clean up resources when the process exits anyway
+fn drop_client_safely(client: Client) {
+    std::mem::forget(client);
+}
+
+/// `User-Agent` sent by `ReqwestTransport` unless one is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("schlussel/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP response returned by an `HttpTransport`
+///
+/// Build one with `new` and add headers with `with_header`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+    /// Response headers; only `Retry-After` is used so far
+    headers: Vec<(String, String)>,
+}
+
+impl HttpResponse {
+    /// Create a new response without headers
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Add a response header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Get the first header named `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Delay requested by a `Retry-After` header in seconds
+    ///
+    /// The HTTP-date form isn't supported and yields `None`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.header("Retry-After")?
+            .trim()
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+
+    /// Check if the status code is in the 2xx range
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Deserialize the body as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+/// Transport used by `OAuthClient` to talk to the authorization server
+///
+/// The default implementation is `ReqwestTransport`. Provide your own to route
+/// requests through a custom HTTP stack, or to return canned responses in tests.
+///
+/// # Example
+///
+/// ```
+/// use schlussel::http::{HttpResponse, HttpTransport};
+/// use schlussel::error::Result;
+///
+/// struct CannedTransport;
+///
+/// impl HttpTransport for CannedTransport {
+///     fn post_form(&self, _url: &str, _params: &[(&str, &str)]) -> Result<HttpResponse> {
+///         Ok(HttpResponse::new(
+///             200,
+///             r#"{"access_token":"abc","token_type":"Bearer"}"#,
+///         ))
+///     }
+/// }
+/// ```
+pub trait HttpTransport: Send + Sync {
+    /// Send a `application/x-www-form-urlencoded` POST request
+    fn post_form(&self, url: &str, params: &[(&str, &str)]) -> Result<HttpResponse>;
+
+    /// Send a `application/x-www-form-urlencoded` POST request with extra headers
+    ///
+    /// Used for HTTP Basic client authentication at the token endpoint. The
+    /// default implementation forwards to `post_form` when there are no headers
+    /// and returns an error otherwise.
+    fn post_form_with_headers(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+        headers: &[(&str, &str)],
+    ) -> Result<HttpResponse> {
+        if headers.is_empty() {
+            return self.post_form(url, params);
+        }
+        Err(OAuthError::Unsupported(format!(
+            "POST {} with headers is not supported by this transport",
+            url
+        )))
+    }
+
+    /// Send a `application/x-www-form-urlencoded` POST request that gives up after `timeout`
+    ///
+    /// Used for device flow polling. A timeout should surface as an
+    /// `OAuthError::HttpError` whose `is_timeout()` is true. The default
+    /// implementation forwards to `post_form` without a timeout.
+    fn post_form_with_timeout(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+        _timeout: Duration,
+    ) -> Result<HttpResponse> {
+        self.post_form(url, params)
+    }
+
+    /// Send a GET request with the given headers
+    ///
+    /// Used for endpoints like OIDC userinfo. The default implementation
+    /// returns `OAuthError::Unsupported`, so transports that only need the
+    /// token endpoint don't have to implement it.
+    fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<HttpResponse> {
+        Err(OAuthError::Unsupported(format!(
+            "GET {} is not supported by this transport",
+            url
+        )))
+    }
+}
+
+/// Default transport backed by `reqwest`'s blocking client
+///
+/// Proxies from `HTTP_PROXY`/`HTTPS_PROXY` are honored unless an explicit
+/// proxy is set with `with_proxy`. Only outgoing requests to the authorization
+/// server are proxied; the loopback callback server never goes through a proxy.
+#[derive(Debug, Default, Clone)]
+pub struct ReqwestTransport {
+    proxy: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl ReqwestTransport {
+    /// Create a transport using the environment's proxy settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send all requests through `proxy`, e.g. `http://proxy.corp:3128`
+    ///
+    /// Hosts listed in `NO_PROXY` still bypass the proxy.
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Identify requests with `user_agent` instead of [`DEFAULT_USER_AGENT`]
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Create an HTTP client for making requests
+    ///
+    /// This must be called from a non-async context because reqwest::blocking::Client::new()
+    /// creates an internal tokio runtime, which is not allowed in async contexts.
+    fn create_http_client(&self) -> Result<Client> {
+        let mut builder =
+            Client::builder().user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)?.no_proxy(reqwest::NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// POST a form with optional extra headers and timeout
+    fn send_form(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<HttpResponse> {
+        let http_client = self.create_http_client()?;
+        let mut request = http_client.post(url).form(params);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let result = request.send();
+
+        // Safely drop client to avoid runtime issues in async contexts
+        drop_client_safely(http_client);
+
+        into_http_response(result?)
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn post_form(&self, url: &str, params: &[(&str, &str)]) -> Result<HttpResponse> {
+        self.post_form_with_headers(url, params, &[])
+    }
+
+    fn post_form_with_headers(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+        headers: &[(&str, &str)],
+    ) -> Result<HttpResponse> {
+        self.send_form(url, params, headers, None)
+    }
+
+    fn post_form_with_timeout(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<HttpResponse> {
+        self.send_form(url, params, &[], Some(timeout))
+    }
+
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpResponse> {
+        let http_client = self.create_http_client()?;
+        let mut request = http_client.get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let result = request.send();
+
+        // Safely drop client to avoid runtime issues in async contexts
+        drop_client_safely(http_client);
+
+        into_http_response(result?)
+    }
+}
+
+/// Read status, headers and body of a reqwest response
+fn into_http_response(response: reqwest::blocking::Response) -> Result<HttpResponse> {
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.text()?;
+
+    Ok(HttpResponse {
+        status,
+        body,
+        headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_response_is_success() {
+        assert!(HttpResponse::new(200, "").is_success());
+        assert!(HttpResponse::new(204, "").is_success());
+        assert!(!HttpResponse::new(400, "").is_success());
+        assert!(!HttpResponse::new(500, "").is_success());
+    }
+
+    #[test]
+    fn test_reqwest_transport_proxy() {
+        let transport = ReqwestTransport::new().with_proxy("http://proxy.example.com:3128");
+        drop_client_safely(transport.create_http_client().unwrap());
+
+        let transport = ReqwestTransport::new().with_proxy("http://bad host:3128");
+        assert!(transport.create_http_client().is_err());
+    }
+
+    #[test]
+    fn test_reqwest_transport_user_agent() {
+        let server = crate::testing::MockOAuthServer::start().unwrap();
+
+        ReqwestTransport::new()
+            .post_form(&server.token_endpoint(), &[])
+            .unwrap();
+        ReqwestTransport::new()
+            .with_user_agent("my-cli/1.2.3")
+            .post_form(&server.token_endpoint(), &[])
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("User-Agent"), Some(DEFAULT_USER_AGENT));
+        assert_eq!(requests[1].header("User-Agent"), Some("my-cli/1.2.3"));
+    }
+
+    #[test]
+    fn test_http_response_json() {
+        let response = HttpResponse::new(200, r#"{"value": 42}"#);
+        let value: serde_json::Value = response.json().unwrap();
+        assert_eq!(value["value"], 42);
+
+        let invalid = HttpResponse::new(200, "not json");
+        assert!(invalid.json::<serde_json::Value>().is_err());
+    }
+}
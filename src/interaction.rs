@@ -0,0 +1,149 @@
+/// User-facing hooks for interactive OAuth flows
+use crate::oauth::DeviceAuthorizationResponse;
+use std::io::Write;
+use std::time::Duration;
+
+/// Hooks for the parts of `authorize()` and `authorize_device()` that talk to the user
+///
+/// The default `StdoutInteraction` prints instructions to stdout and opens the
+/// system browser. Implement this trait to render the prompts in your own UI
+/// (or to capture them in tests).
+pub trait UserInteraction: Send + Sync {
+    /// Open `url` in a browser, returning whether it was opened
+    ///
+    /// Only called when a browser is usable in the current environment
+    /// (see [`browser_available`]).
+    fn open_browser(&self, url: &str) -> bool {
+        webbrowser::open(url).is_ok()
+    }
+
+    /// Show the authorization URL for the authorization code flow
+    fn show_authorization_url(&self, url: &str, browser_opened: bool);
+
+    /// Show the verification URI and user code for the Device Code Flow
+    fn show_device_code(&self, device_auth: &DeviceAuthorizationResponse, browser_opened: bool);
+
+    /// Called before each Device Code Flow token poll
+    ///
+    /// `attempt` starts at 1, `elapsed` is the time since polling started and
+    /// `expires_in` is the lifetime of the device code, so a CLI can render a
+    /// countdown or spinner. Does nothing by default.
+    fn on_poll(&self, _attempt: u32, _elapsed: Duration, _expires_in: Duration) {}
+
+    /// Called when the token endpoint granted fewer scopes than configured
+    ///
+    /// `missing` lists the requested scopes that aren't part of the token (see
+    /// `Token::missing_scopes`). The token is still returned. Does nothing by
+    /// default.
+    fn on_missing_scopes(&self, _missing: &[String]) {}
+
+    /// Called when an authorization URL is built with PKCE disabled and no
+    /// client secret, so nothing authenticates the code exchange
+    ///
+    /// Does nothing by default.
+    fn on_unauthenticated_exchange(&self) {}
+}
+
+/// Default interaction printing instructions to stdout
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutInteraction;
+
+impl UserInteraction for StdoutInteraction {
+    fn show_authorization_url(&self, url: &str, browser_opened: bool) {
+        println!("\n=== Authorization Required ===");
+        if browser_opened {
+            println!("Opening browser for authorization...");
+            println!("If the browser doesn't open, visit: {}", url);
+        } else {
+            println!("Open this URL manually: {}", url);
+        }
+        println!("Waiting for authorization...");
+    }
+
+    fn show_device_code(&self, device_auth: &DeviceAuthorizationResponse, browser_opened: bool) {
+        println!("\n=== Device Authorization ===");
+        println!("Please visit: {}", device_auth.verification_uri);
+        println!("And enter code: {}", device_auth.user_code);
+
+        if let Some(complete_uri) = &device_auth.verification_uri_complete {
+            println!("\nOr visit this URL directly:");
+            println!("{}", complete_uri);
+        }
+
+        if browser_opened {
+            println!("\nOpening browser...");
+        }
+
+        println!("\nWaiting for authorization...");
+    }
+
+    fn on_poll(&self, _attempt: u32, _elapsed: Duration, _expires_in: Duration) {
+        print!(".");
+        let _ = std::io::stdout().flush();
+    }
+
+    fn on_missing_scopes(&self, missing: &[String]) {
+        eprintln!(
+            "Warning: the authorization server did not grant: {}",
+            missing.join(" ")
+        );
+    }
+
+    fn on_unauthenticated_exchange(&self) {
+        eprintln!(
+            "Warning: PKCE is disabled and no client_secret is configured; \
+             the authorization code exchange is unauthenticated"
+        );
+    }
+}
+
+/// Check whether opening a browser makes sense in the current environment
+///
+/// Returns `false` when:
+/// - `SCHLUSSEL_NO_BROWSER` or `NO_BROWSER` is set
+/// - on Unix, the session is remote (`SSH_CONNECTION` is set) and there is no
+///   display (`DISPLAY` and `WAYLAND_DISPLAY` are unset)
+pub fn browser_available() -> bool {
+    browser_available_with(|name| std::env::var_os(name).is_some())
+}
+
+/// Same as [`browser_available`], looking variables up with `is_set`
+pub(crate) fn browser_available_with(is_set: impl Fn(&str) -> bool) -> bool {
+    if is_set("SCHLUSSEL_NO_BROWSER") || is_set("NO_BROWSER") {
+        return false;
+    }
+
+    #[cfg(unix)]
+    if is_set("SSH_CONNECTION") && !is_set("DISPLAY") && !is_set("WAYLAND_DISPLAY") {
+        return false;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |name| vars.contains(&name)
+    }
+
+    #[test]
+    fn test_browser_disabled_by_env() {
+        assert!(browser_available_with(env(&[])));
+        assert!(!browser_available_with(env(&["SCHLUSSEL_NO_BROWSER"])));
+        assert!(!browser_available_with(env(&["NO_BROWSER", "DISPLAY"])));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_browser_unavailable_over_headless_ssh() {
+        assert!(!browser_available_with(env(&["SSH_CONNECTION"])));
+        assert!(browser_available_with(env(&["SSH_CONNECTION", "DISPLAY"])));
+        assert!(browser_available_with(env(&[
+            "SSH_CONNECTION",
+            "WAYLAND_DISPLAY"
+        ])));
+    }
+}
@@ -17,39 +17,55 @@
 //! use std::sync::Arc;
 //!
 //! let storage = Arc::new(MemoryStorage::new());
-//! let config = OAuthConfig {
-//!     client_id: "your-client-id".to_string(),
-//!     authorization_endpoint: "https://auth.example.com/authorize".to_string(),
-//!     token_endpoint: "https://auth.example.com/token".to_string(),
-//!     redirect_uri: "http://localhost:8080/callback".to_string(),
-//!     scope: Some("read write".to_string()),
-//!     device_authorization_endpoint: None,
-//! };
+//! let config = OAuthConfig::new(
+//!     "your-client-id",
+//!     "https://auth.example.com/authorize",
+//!     "https://auth.example.com/token",
+//!     "http://localhost:8080/callback",
+//! )
+//! .with_scope("read write");
 //!
 //! let client = OAuthClient::new(config, storage);
 //! let result = client.start_auth_flow().unwrap();
 //! println!("Authorization URL: {}", result.url);
 //! ```
 
+#[cfg(feature = "async")]
+pub mod async_storage;
 pub mod callback;
 pub mod error;
 pub mod ffi;
+pub mod http;
+pub mod interaction;
 pub mod lock;
 pub mod oauth;
 pub mod pkce;
+pub mod scope;
 pub mod session;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 /// Prelude module for convenient imports
 pub mod prelude {
-    pub use crate::callback::{CallbackResult, CallbackServer};
+    #[cfg(feature = "async")]
+    pub use crate::async_storage::{AsyncMemoryStorage, AsyncSessionStorage, BlockingStorage};
+    pub use crate::callback::{CallbackResult, CallbackServer, CodeReceiver};
     pub use crate::error::{OAuthError, Result};
+    pub use crate::http::{HttpResponse, HttpTransport, ReqwestTransport};
+    pub use crate::interaction::{StdoutInteraction, UserInteraction};
     pub use crate::lock::{RefreshLock, RefreshLockManager};
     pub use crate::oauth::{
-        AuthFlowResult, DeviceAuthorizationResponse, OAuthClient, OAuthConfig, TokenRefresher,
+        AuthFlowResult, ClientAuthMethod, DeviceAuthorizationResponse, DeviceFlow, DynOAuthClient,
+        DynTokenRefresher, LoginMethod, OAuthClient, OAuthConfig, RefreshObserver, RequestPlan,
+        TokenFreshness, TokenRefresher,
     };
     pub use crate::pkce::Pkce;
+    pub use crate::scope::Scopes;
+    #[cfg(any(test, feature = "testing"))]
+    pub use crate::session::MockStorage;
     pub use crate::session::{
-        FileStorage, MemoryStorage, SecureStorage, Session, SessionStorage, Token,
+        Account, FileStorage, MemoryStorage, SecureStorage, Session, SessionInfo, SessionStorage,
+        StorageBackend, StorageError, Token,
     };
 }
 
@@ -60,17 +76,16 @@ mod tests {
 
     #[test]
     fn test_full_oauth_flow() {
-        use std::time::{SystemTime, UNIX_EPOCH};
+        use crate::session::unix_now;
 
         let storage = Arc::new(MemoryStorage::new());
-        let config = OAuthConfig {
-            client_id: "test-client".to_string(),
-            authorization_endpoint: "https://auth.example.com/authorize".to_string(),
-            token_endpoint: "https://auth.example.com/token".to_string(),
-            redirect_uri: "http://localhost:8080/callback".to_string(),
-            scope: Some("read write".to_string()),
-            device_authorization_endpoint: None,
-        };
+        let config = OAuthConfig::new(
+            "test-client",
+            "https://auth.example.com/authorize",
+            "https://auth.example.com/token",
+            "http://localhost:8080/callback",
+        )
+        .with_scope("read write");
 
         let client = Arc::new(OAuthClient::new(config, storage.clone()));
         let result = client.start_auth_flow().unwrap();
@@ -83,10 +98,7 @@ mod tests {
         assert!(session.is_some());
 
         // Test token storage and retrieval
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = unix_now();
 
         let token = Token {
             access_token: "test_access_token".to_string(),
@@ -94,7 +106,8 @@ mod tests {
             token_type: "Bearer".to_string(),
             expires_in: Some(3600),
             expires_at: Some(now + 3600),
-            scope: Some("read write".to_string()),
+            scope: Some("read write".into()),
+            token_endpoint: None,
         };
 
         client.save_token("test-key", token.clone()).unwrap();
@@ -1,8 +1,10 @@
 /// Cross-process locking for token refresh coordination
-use crate::error::Result;
+use crate::error::{OAuthError, Result};
+use crate::session::unix_now;
 use fs2::FileExt;
 use std::fs::{self, File, OpenOptions};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Manager for cross-process refresh locks
 ///
@@ -30,10 +32,81 @@ impl RefreshLockManager {
     }
 
     /// Create a lock manager for a specific application
+    ///
+    /// Uses the first writable directory of:
+    /// 1. `$XDG_RUNTIME_DIR/schlussel-locks/<app_name>`
+    /// 2. a user-specific directory in the temp dir
+    /// 3. `<data dir>/<app_name>/locks`
+    ///
+    /// Returns `OAuthError::LockingUnavailable` if none is writable (e.g. in a
+    /// restricted sandbox), so callers can fall back to in-process locking
+    /// with `TokenRefresher::new`.
     pub fn for_app(app_name: &str) -> Result<Self> {
-        let mut lock_dir = Self::default_lock_dir()?;
-        lock_dir.push(app_name);
-        Self::new(lock_dir)
+        Self::first_writable(&Self::app_lock_dirs(app_name))
+    }
+
+    fn app_lock_dirs(app_name: &str) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+            dirs.push(
+                PathBuf::from(runtime_dir)
+                    .join("schlussel-locks")
+                    .join(app_name),
+            );
+        }
+
+        dirs.push(
+            std::env::temp_dir()
+                .join(format!("schlussel-locks-{}", Self::get_user_id()))
+                .join(app_name),
+        );
+
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(dirs::data_dir);
+        if let Some(data_dir) = data_dir {
+            dirs.push(data_dir.join(app_name).join("locks"));
+        }
+
+        dirs
+    }
+
+    /// Create a lock manager in the first candidate directory that can hold lock files
+    fn first_writable(candidates: &[PathBuf]) -> Result<Self> {
+        let mut failures = Vec::new();
+
+        for dir in candidates {
+            match Self::check_writable(dir) {
+                Ok(()) => {
+                    return Ok(Self {
+                        lock_dir: dir.clone(),
+                    })
+                }
+                Err(e) => failures.push(format!("{}: {}", dir.display(), e)),
+            }
+        }
+
+        Err(OAuthError::LockingUnavailable(format!(
+            "no writable lock directory ({})",
+            failures.join("; ")
+        )))
+    }
+
+    fn check_writable(dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let probe = dir.join(format!(".probe-{}", std::process::id()));
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&probe)?;
+        fs::remove_file(&probe)
+    }
+
+    /// Get the directory where lock files are created
+    pub fn lock_dir(&self) -> &Path {
+        &self.lock_dir
     }
 
     fn default_lock_dir() -> Result<PathBuf> {
@@ -136,11 +209,108 @@ impl RefreshLockManager {
         }
     }
 
+    /// List the lock files in the lock directory
+    ///
+    /// Useful to debug stuck refreshes. Includes both held locks and leftovers
+    /// from processes that exited without cleaning up.
+    pub fn list_locks(&self) -> Result<Vec<PathBuf>> {
+        let mut locks = Vec::new();
+        for entry in fs::read_dir(&self.lock_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "lock") && path.is_file() {
+                locks.push(path);
+            }
+        }
+        locks.sort();
+        Ok(locks)
+    }
+
+    /// Remove lock files left behind by crashed processes
+    ///
+    /// A lock file is removed only if it was last modified at least
+    /// `older_than` ago and nobody holds the lock, confirmed by briefly
+    /// acquiring it. Returns the number of removed files.
+    pub fn clear_stale(&self, older_than: Duration) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+
+        for path in self.list_locks()? {
+            let modified = fs::metadata(&path)?.modified()?;
+            let age = now.duration_since(modified).unwrap_or_default();
+            if age < older_than {
+                continue;
+            }
+
+            let file = match OpenOptions::new().read(true).write(true).open(&path) {
+                Ok(file) => file,
+                // Released and removed concurrently
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if file.try_lock_exclusive().is_ok() {
+                let result = fs::remove_file(&path);
+                let _ = file.unlock();
+                match result {
+                    Ok(()) => removed += 1,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Record that refreshing `key` failed with `error`
+    ///
+    /// Stored in a sidecar file next to the lock so other processes can see
+    /// the failure and back off instead of retrying immediately.
+    pub fn record_failure(&self, key: &str, error: &str) -> Result<()> {
+        let now = unix_now();
+        fs::write(self.failure_path(key), format!("{}\n{}", now, error))?;
+        Ok(())
+    }
+
+    /// Time and error message of the last recorded refresh failure for `key`
+    ///
+    /// Returns `None` if no failure is recorded or the sidecar file is unreadable.
+    pub fn last_failure(&self, key: &str) -> Result<Option<(SystemTime, String)>> {
+        let content = match fs::read_to_string(self.failure_path(key)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let (timestamp, error) = content.split_once('\n').unwrap_or((&content, ""));
+        Ok(timestamp
+            .trim()
+            .parse()
+            .ok()
+            .map(|secs| (UNIX_EPOCH + Duration::from_secs(secs), error.to_string())))
+    }
+
+    /// Forget the recorded refresh failure for `key`, e.g. after a successful refresh
+    pub fn clear_failure(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.failure_path(key)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     fn lock_path(&self, key: &str) -> PathBuf {
-        // Sanitize the key for use in filename
-        let safe_key = key.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
-        self.lock_dir.join(format!("{}.lock", safe_key))
+        self.lock_dir.join(format!("{}.lock", sanitize_key(key)))
     }
+
+    fn failure_path(&self, key: &str) -> PathBuf {
+        self.lock_dir
+            .join(format!("{}.last-failure", sanitize_key(key)))
+    }
+}
+
+/// Sanitize a token key for use in a filename
+fn sanitize_key(key: &str) -> String {
+    key.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
 }
 
 /// RAII guard for a refresh lock
@@ -176,14 +346,14 @@ mod tests {
     use super::*;
     use std::sync::Arc;
     use std::thread;
-    use std::time::Duration;
 
     #[test]
     fn test_lock_manager_creation() {
         let temp_dir = std::env::temp_dir().join(format!("test_locks_{}", rand::random::<u32>()));
-        let _manager = RefreshLockManager::new(temp_dir.clone()).unwrap();
+        let manager = RefreshLockManager::new(temp_dir.clone()).unwrap();
 
         assert!(temp_dir.exists());
+        assert_eq!(manager.lock_dir(), temp_dir.as_path());
 
         // Cleanup
         fs::remove_dir_all(temp_dir).ok();
@@ -266,6 +436,74 @@ mod tests {
         fs::remove_dir_all(temp_dir).ok();
     }
 
+    #[test]
+    fn test_list_locks_and_clear_stale() {
+        let temp_dir = std::env::temp_dir().join(format!("test_locks_{}", rand::random::<u32>()));
+        let manager = RefreshLockManager::new(temp_dir.clone()).unwrap();
+
+        // Leftovers from crashed processes, backdated by an hour
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        for name in ["crashed-a.lock", "crashed-b.lock"] {
+            File::create(temp_dir.join(name))
+                .unwrap()
+                .set_modified(hour_ago)
+                .unwrap();
+        }
+        // A recent leftover and a file that isn't a lock
+        File::create(temp_dir.join("recent.lock")).unwrap();
+        File::create(temp_dir.join("notes.txt")).unwrap();
+        // A lock that is currently held
+        let held = manager.acquire_lock("held").unwrap();
+        held.file.as_ref().unwrap().set_modified(hour_ago).unwrap();
+
+        let locks = manager.list_locks().unwrap();
+        assert_eq!(locks.len(), 4);
+        assert!(locks.iter().all(|p| p.extension().unwrap() == "lock"));
+
+        // Only unheld locks older than the threshold are removed
+        assert_eq!(manager.clear_stale(Duration::from_secs(600)).unwrap(), 2);
+        let locks = manager.list_locks().unwrap();
+        assert_eq!(
+            locks,
+            vec![held.path().to_path_buf(), temp_dir.join("recent.lock")]
+        );
+
+        drop(held);
+        assert_eq!(manager.clear_stale(Duration::ZERO).unwrap(), 1);
+        assert!(manager.list_locks().unwrap().is_empty());
+
+        // Cleanup
+        fs::remove_dir_all(temp_dir).ok();
+    }
+
+    #[test]
+    fn test_for_app_falls_back_to_writable_dir() {
+        let temp_dir = std::env::temp_dir().join(format!("test_locks_{}", rand::random::<u32>()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        // A directory can't be created below a regular file, even as root
+        let blocker = temp_dir.join("not-a-dir");
+        File::create(&blocker).unwrap();
+        let unwritable = blocker.join("locks");
+        let writable = temp_dir.join("fallback");
+
+        let manager =
+            RefreshLockManager::first_writable(&[unwritable.clone(), writable.clone()]).unwrap();
+        assert_eq!(manager.lock_dir(), writable.as_path());
+        assert!(manager.list_locks().unwrap().is_empty());
+
+        let result = RefreshLockManager::first_writable(&[unwritable]);
+        match result {
+            Err(OAuthError::LockingUnavailable(message)) => {
+                assert!(message.contains("not-a-dir"));
+            }
+            other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        // Cleanup
+        fs::remove_dir_all(temp_dir).ok();
+    }
+
     #[test]
     fn test_key_sanitization() {
         let temp_dir = std::env::temp_dir().join(format!("test_locks_{}", rand::random::<u32>()));
@@ -282,4 +520,29 @@ mod tests {
         // Cleanup
         fs::remove_dir_all(temp_dir).ok();
     }
+
+    #[test]
+    fn test_record_and_clear_failure() {
+        let temp_dir = std::env::temp_dir().join(format!("test_locks_{}", rand::random::<u32>()));
+        let manager = RefreshLockManager::new(temp_dir.clone()).unwrap();
+        assert!(manager.last_failure("domain.com:user").unwrap().is_none());
+
+        let before = SystemTime::now() - Duration::from_secs(1);
+        manager
+            .record_failure("domain.com:user", "server error\nwith details")
+            .unwrap();
+        let (failed_at, error) = manager.last_failure("domain.com:user").unwrap().unwrap();
+        assert!(failed_at >= before);
+        assert_eq!(error, "server error\nwith details");
+
+        // The sidecar file isn't mistaken for a lock
+        assert!(manager.list_locks().unwrap().is_empty());
+
+        manager.clear_failure("domain.com:user").unwrap();
+        assert!(manager.last_failure("domain.com:user").unwrap().is_none());
+        manager.clear_failure("domain.com:user").unwrap();
+
+        // Cleanup
+        fs::remove_dir_all(temp_dir).ok();
+    }
 }
@@ -1,5 +1,6 @@
 /// PKCE (Proof Key for Code Exchange) implementation
 /// RFC 7636: https://tools.ietf.org/html/rfc7636
+use crate::error::{OAuthError, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use rand::Rng;
 use sha2::{Digest, Sha256};
@@ -30,9 +31,68 @@ impl Pkce {
         let mut rng = rand::thread_rng();
         let random_bytes: [u8; 32] = rng.gen();
 
+        Self::from_random_bytes(&random_bytes)
+    }
+
+    /// Generate a PKCE challenge pair from a given number of random bytes
+    ///
+    /// RFC 7636 requires the code verifier to be 43 to 128 characters long.
+    /// The verifier is the base64url encoding (without padding) of the random
+    /// bytes, so `n` bytes produce `ceil(n * 4 / 3)` characters:
+    ///
+    /// - 32 bytes → 43 characters (minimum, same as `generate()`)
+    /// - 64 bytes → 86 characters
+    /// - 96 bytes → 128 characters (maximum)
+    ///
+    /// Returns an error if `bytes` is outside of `32..=96`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use schlussel::pkce::Pkce;
+    ///
+    /// let pkce = Pkce::generate_with_length(96).unwrap();
+    /// assert_eq!(pkce.code_verifier().len(), 128);
+    ///
+    /// assert!(Pkce::generate_with_length(16).is_err());
+    /// ```
+    pub fn generate_with_length(bytes: usize) -> Result<Self> {
+        // 32..=96 bytes encode to the 43..=128 characters RFC 7636 allows
+        if !(32..=96).contains(&bytes) {
+            return Err(OAuthError::InvalidParameter(format!(
+                "{} random bytes don't produce a 43 to 128 character code verifier (RFC 7636); use 32 to 96",
+                bytes
+            )));
+        }
+
+        let mut rng = rand::thread_rng();
+        let random_bytes: Vec<u8> = (0..bytes).map(|_| rng.gen()).collect();
+
+        Ok(Self::from_random_bytes(&random_bytes))
+    }
+
+    pub(crate) fn from_random_bytes(random_bytes: &[u8]) -> Self {
         // Base64 URL encode without padding
-        let code_verifier = URL_SAFE_NO_PAD.encode(random_bytes);
+        Self::from_verifier(URL_SAFE_NO_PAD.encode(random_bytes))
+    }
 
+    /// Create a PKCE challenge pair from an existing code verifier
+    ///
+    /// Derives the S256 code challenge from `code_verifier`. Useful to rebuild
+    /// the pair from a stored verifier or to get reproducible values in tests.
+    /// The verifier is used as-is; RFC 7636 requires 43 to 128 characters from
+    /// `[A-Za-z0-9-._~]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use schlussel::pkce::Pkce;
+    ///
+    /// // Example from RFC 7636 Appendix B
+    /// let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string());
+    /// assert_eq!(pkce.code_challenge(), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    /// ```
+    pub fn from_verifier(code_verifier: String) -> Self {
         // Create SHA256 hash of code_verifier
         let mut hasher = Sha256::new();
         hasher.update(code_verifier.as_bytes());
@@ -88,6 +148,41 @@ mod tests {
         assert_ne!(pkce1.code_challenge(), pkce2.code_challenge());
     }
 
+    #[test]
+    fn test_from_verifier_matches_generate() {
+        let pkce = Pkce::generate();
+        let rebuilt = Pkce::from_verifier(pkce.code_verifier().to_string());
+        assert_eq!(rebuilt.code_verifier(), pkce.code_verifier());
+        assert_eq!(rebuilt.code_challenge(), pkce.code_challenge());
+    }
+
+    #[test]
+    fn test_generate_with_length_bounds() {
+        let min = Pkce::generate_with_length(32).unwrap();
+        assert_eq!(min.code_verifier().len(), 43);
+        assert_eq!(min.code_challenge().len(), 43);
+
+        let max = Pkce::generate_with_length(96).unwrap();
+        assert_eq!(max.code_verifier().len(), 128);
+        assert_eq!(max.code_challenge().len(), 43);
+    }
+
+    #[test]
+    fn test_generate_with_length_out_of_range() {
+        assert!(matches!(
+            Pkce::generate_with_length(31),
+            Err(OAuthError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Pkce::generate_with_length(97),
+            Err(OAuthError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Pkce::generate_with_length(usize::MAX),
+            Err(OAuthError::InvalidParameter(_))
+        ));
+    }
+
     #[test]
     fn test_code_challenge_method() {
         assert_eq!(Pkce::code_challenge_method(), "S256");
@@ -0,0 +1,182 @@
+/// OAuth scope sets
+/// RFC 6749 Section 3.3: https://tools.ietf.org/html/rfc6749#section-3.3
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// Set of OAuth scopes, written as a space-delimited string
+///
+/// The order of scopes carries no meaning (RFC 6749 Section 3.3), so they are
+/// kept sorted and duplicates are dropped. Converts from `&str`/`String` and
+/// back into `String`, and serializes as the space-delimited string, so
+/// stored tokens keep their format.
+///
+/// # Examples
+///
+/// ```
+/// use schlussel::scope::Scopes;
+///
+/// let granted: Scopes = "openid email".parse().unwrap();
+/// let requested = Scopes::from("email");
+/// assert!(requested.is_subset(&granted));
+///
+/// let wider = granted.union(&Scopes::from("drive"));
+/// assert_eq!(wider.to_string(), "drive email openid");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Scopes(BTreeSet<String>);
+
+impl Scopes {
+    /// Create an empty scope set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether `scope` is part of the set
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Add a scope, returning whether it was new
+    pub fn insert(&mut self, scope: impl Into<String>) -> bool {
+        self.0.insert(scope.into())
+    }
+
+    /// Scopes in either set
+    pub fn union(&self, other: &Scopes) -> Scopes {
+        Scopes(self.0.union(&other.0).cloned().collect())
+    }
+
+    /// Check whether every scope in this set is also in `other`
+    pub fn is_subset(&self, other: &Scopes) -> bool {
+        self.0.is_subset(&other.0)
+    }
+
+    /// Scopes in this set that are missing from `other`
+    pub fn difference<'a>(&'a self, other: &'a Scopes) -> impl Iterator<Item = &'a str> {
+        self.0.difference(&other.0).map(String::as_str)
+    }
+
+    /// Check whether the set has no scopes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of scopes in the set
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Iterate over the scopes in sorted order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl FromStr for Scopes {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Scopes::from(s))
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, scope) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(scope)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for Scopes {
+    fn from(s: &str) -> Self {
+        s.split_whitespace().collect()
+    }
+}
+
+impl From<String> for Scopes {
+    fn from(s: String) -> Self {
+        Scopes::from(s.as_str())
+    }
+}
+
+impl From<&String> for Scopes {
+    fn from(s: &String) -> Self {
+        Scopes::from(s.as_str())
+    }
+}
+
+impl From<Scopes> for String {
+    fn from(scopes: Scopes) -> Self {
+        scopes.to_string()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Scopes {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Scopes(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Scopes::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_round_trip() {
+        let scopes: Scopes = "  write read\tread  ".parse().unwrap();
+        assert_eq!(scopes.len(), 2);
+        assert_eq!(scopes.to_string(), "read write");
+        assert_eq!(scopes.to_string().parse::<Scopes>().unwrap(), scopes);
+        assert_eq!(String::from(scopes), "read write");
+
+        let empty = Scopes::from("");
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_string(), "");
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let granted = Scopes::from("openid email profile");
+        let narrow = Scopes::from("email");
+        let wide = Scopes::from("email drive");
+
+        assert!(granted.contains("openid"));
+        assert!(!granted.contains("drive"));
+        assert!(narrow.is_subset(&granted));
+        assert!(!wide.is_subset(&granted));
+        assert_eq!(wide.difference(&granted).collect::<Vec<_>>(), vec!["drive"]);
+        assert_eq!(
+            granted.union(&wide).to_string(),
+            "drive email openid profile"
+        );
+    }
+
+    #[test]
+    fn test_serde_uses_space_delimited_string() {
+        let scopes = Scopes::from("read write");
+        assert_eq!(serde_json::to_string(&scopes).unwrap(), r#""read write""#);
+
+        let parsed: Scopes = serde_json::from_str(r#""write read""#).unwrap();
+        assert_eq!(parsed, scopes);
+    }
+}
@@ -0,0 +1,412 @@
+/// Local OAuth server for testing `OAuthClient` end to end
+use crate::callback::parse_query_params;
+use crate::oauth::OAuthConfig;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const AUTHORIZATION_PATH: &str = "/authorize";
+const TOKEN_PATH: &str = "/token";
+const DEVICE_AUTHORIZATION_PATH: &str = "/device/code";
+
+/// Request received by a `MockOAuthServer`
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    /// Request path without the query string
+    pub path: String,
+    /// Form parameters from the body (or query string), URL-decoded
+    pub params: HashMap<String, String>,
+    /// Request headers, with lowercased names
+    pub headers: HashMap<String, String>,
+}
+
+impl MockRequest {
+    /// Get a form parameter by name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Get a header by name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Queued response: status, body and how long to wait before sending it
+type QueuedResponse = (u16, String, Duration);
+
+#[derive(Debug, Default)]
+struct MockState {
+    token_responses: VecDeque<QueuedResponse>,
+    device_responses: VecDeque<QueuedResponse>,
+    requests: Vec<MockRequest>,
+}
+
+/// OAuth authorization server on a random loopback port
+///
+/// Serves a token endpoint and a device authorization endpoint with canned
+/// JSON responses, and records every request so tests can assert on the
+/// parameters and headers `OAuthClient` sent. Responses queued with
+/// `respond_token` / `respond_device` are returned in order; once a queue is
+/// empty the endpoint answers with a successful default response.
+///
+/// The server stops when dropped. Available in unit tests and with the
+/// `testing` feature.
+///
+/// # Example
+///
+/// ```
+/// use schlussel::prelude::*;
+/// use schlussel::testing::MockOAuthServer;
+/// use std::sync::Arc;
+///
+/// let server = MockOAuthServer::start().unwrap();
+/// let client = OAuthClient::new(server.config("my-client"), Arc::new(MemoryStorage::new()));
+///
+/// let flow = client.start_auth_flow().unwrap();
+/// let token = client.exchange_code("code", &flow.state).unwrap();
+/// assert_eq!(token.access_token, "mock_access_token");
+/// assert_eq!(server.requests()[0].param("code"), Some("code"));
+/// ```
+pub struct MockOAuthServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockOAuthServer {
+    /// Start the server on a random available port
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // One thread per connection so a delayed response doesn't block others
+                        let state = state.clone();
+                        thread::spawn(move || {
+                            // A client hanging up early shouldn't stop the server
+                            let _ = handle_connection(stream, addr, &state);
+                        });
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// Base URL of the server, e.g. `http://127.0.0.1:54321`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// URL of the authorization endpoint (answers any GET with `200`)
+    pub fn authorization_endpoint(&self) -> String {
+        format!("{}{}", self.url(), AUTHORIZATION_PATH)
+    }
+
+    /// URL of the token endpoint
+    pub fn token_endpoint(&self) -> String {
+        format!("{}{}", self.url(), TOKEN_PATH)
+    }
+
+    /// URL of the device authorization endpoint
+    pub fn device_authorization_endpoint(&self) -> String {
+        format!("{}{}", self.url(), DEVICE_AUTHORIZATION_PATH)
+    }
+
+    /// Create a configuration pointing at this server's endpoints
+    pub fn config(&self, client_id: impl Into<String>) -> OAuthConfig {
+        OAuthConfig::new(
+            client_id,
+            self.authorization_endpoint(),
+            self.token_endpoint(),
+            "http://127.0.0.1:8080/callback",
+        )
+        .with_device_authorization_endpoint(self.device_authorization_endpoint())
+    }
+
+    /// Queue a response for the next token endpoint request
+    ///
+    /// E.g. `respond_token(400, json!({"error": "authorization_pending"}))`
+    /// to make a device flow poll once more before succeeding.
+    pub fn respond_token(&self, status: u16, body: Value) {
+        self.respond_token_after(Duration::ZERO, status, body);
+    }
+
+    /// Queue a token endpoint response that is only sent after `delay`
+    ///
+    /// Simulates a hanging provider, e.g. to test request timeouts. Other
+    /// requests are still answered while the delayed one waits.
+    pub fn respond_token_after(&self, delay: Duration, status: u16, body: Value) {
+        self.state
+            .lock()
+            .token_responses
+            .push_back((status, body.to_string(), delay));
+    }
+
+    /// Queue a response for the next device authorization request
+    pub fn respond_device(&self, status: u16, body: Value) {
+        self.state
+            .lock()
+            .device_responses
+            .push_back((status, body.to_string(), Duration::ZERO));
+    }
+
+    /// All requests received so far, in order
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().requests.clone()
+    }
+}
+
+impl Drop for MockOAuthServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the blocking accept so the thread sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn default_token_response() -> Value {
+    json!({
+        "access_token": "mock_access_token",
+        "refresh_token": "mock_refresh_token",
+        "token_type": "Bearer",
+        "expires_in": 3600
+    })
+}
+
+fn default_device_response(addr: SocketAddr) -> Value {
+    json!({
+        "device_code": "mock_device_code",
+        "user_code": "MOCK-CODE",
+        "verification_uri": format!("http://{}/device", addr),
+        "expires_in": 600,
+        "interval": 0
+    })
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    state: &Mutex<MockState>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let form = if body.is_empty() {
+        query.to_string()
+    } else {
+        String::from_utf8_lossy(&body).into_owned()
+    };
+
+    let (status, response, delay) = {
+        let mut state = state.lock();
+        state.requests.push(MockRequest {
+            method,
+            path: path.to_string(),
+            params: parse_query_params(&form),
+            headers,
+        });
+
+        match path {
+            TOKEN_PATH => state
+                .token_responses
+                .pop_front()
+                .unwrap_or_else(|| (200, default_token_response().to_string(), Duration::ZERO)),
+            DEVICE_AUTHORIZATION_PATH => state.device_responses.pop_front().unwrap_or_else(|| {
+                (
+                    200,
+                    default_device_response(addr).to_string(),
+                    Duration::ZERO,
+                )
+            }),
+            AUTHORIZATION_PATH => (200, "{}".to_string(), Duration::ZERO),
+            _ => (
+                404,
+                json!({"error": "not_found"}).to_string(),
+                Duration::ZERO,
+            ),
+        }
+    };
+
+    thread::sleep(delay);
+
+    write_response(stream, status, &response)
+}
+
+fn write_response(mut stream: TcpStream, status: u16, body: &str) -> io::Result<()> {
+    let reason = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interaction::UserInteraction;
+    use crate::oauth::{DeviceAuthorizationResponse, OAuthClient};
+    use crate::session::MemoryStorage;
+
+    /// Interaction that neither prints nor opens a browser
+    struct QuietInteraction;
+
+    impl UserInteraction for QuietInteraction {
+        fn open_browser(&self, _url: &str) -> bool {
+            false
+        }
+
+        fn show_authorization_url(&self, _url: &str, _browser_opened: bool) {}
+
+        fn show_device_code(
+            &self,
+            _device_auth: &DeviceAuthorizationResponse,
+            _browser_opened: bool,
+        ) {
+        }
+    }
+
+    #[test]
+    fn test_mock_server_exchange_code() {
+        let server = MockOAuthServer::start().unwrap();
+        server.respond_token(
+            200,
+            json!({"access_token": "exchanged", "token_type": "Bearer", "expires_in": 60}),
+        );
+        let client = OAuthClient::new(server.config("test-client"), Arc::new(MemoryStorage::new()));
+
+        let flow = client.start_auth_flow().unwrap();
+        let token = client.exchange_code("auth-code", &flow.state).unwrap();
+        assert_eq!(token.access_token, "exchanged");
+        assert_eq!(token.expires_in, Some(60));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/token");
+        assert_eq!(requests[0].param("grant_type"), Some("authorization_code"));
+        assert_eq!(requests[0].param("code"), Some("auth-code"));
+        assert_eq!(requests[0].param("client_id"), Some("test-client"));
+        assert_eq!(
+            requests[0].param("code_verifier"),
+            Some(flow.code_verifier.as_str())
+        );
+        assert_eq!(
+            requests[0].header("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+    }
+
+    #[test]
+    fn test_mock_server_authorize_device() {
+        let server = MockOAuthServer::start().unwrap();
+        server.respond_token(400, json!({"error": "authorization_pending"}));
+        let client = OAuthClient::new(
+            server.config("test-client").with_scope("read"),
+            Arc::new(MemoryStorage::new()),
+        )
+        .with_interaction(QuietInteraction);
+
+        let token = client.authorize_device().unwrap();
+        assert_eq!(token.access_token, "mock_access_token");
+        assert_eq!(token.refresh_token.as_deref(), Some("mock_refresh_token"));
+
+        let requests = server.requests();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/device/code", "/token", "/token"]);
+        assert_eq!(requests[0].param("scope"), Some("read"));
+        assert_eq!(requests[2].param("device_code"), Some("mock_device_code"));
+    }
+
+    #[test]
+    fn test_mock_server_receives_configured_user_agent() {
+        let server = MockOAuthServer::start().unwrap();
+        let client = OAuthClient::new(
+            server.config("test-client").with_user_agent("my-cli/1.2.3"),
+            Arc::new(MemoryStorage::new()),
+        )
+        .with_interaction(QuietInteraction);
+
+        let flow = client.start_auth_flow().unwrap();
+        client.exchange_code("code", &flow.state).unwrap();
+        let token = client.authorize_device().unwrap();
+        client
+            .refresh_token(token.refresh_token.as_deref().unwrap())
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        for request in requests {
+            assert_eq!(request.header("User-Agent"), Some("my-cli/1.2.3"));
+        }
+    }
+
+    #[test]
+    fn test_mock_server_unknown_path() {
+        let server = MockOAuthServer::start().unwrap();
+        let response = reqwest::blocking::get(format!("{}/nope", server.url())).unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+    }
+}
@@ -51,6 +51,19 @@ public class SchlusselClient {
         let error = schlussel_save_token(handle, key, token.handle)
         return error == SCHLUSSEL_OK
     }
+
+    /// Get a stored token, refreshing it first if it is expired
+    ///
+    /// This blocks while the refresh request runs, so call it off the main thread.
+    ///
+    /// - Parameter key: Token key (e.g., "github.com:user")
+    /// - Returns: A valid token, or nil if none is stored or the refresh failed
+    public func getValidToken(key: String) -> SchlusselToken? {
+        guard let tokenHandle = schlussel_get_valid_token(handle, key) else {
+            return nil
+        }
+        return SchlusselToken(handle: tokenHandle)
+    }
 }
 
 /// Represents an OAuth token
@@ -74,10 +87,39 @@ public class SchlusselToken {
         return String(cString: cString)
     }
 
+    /// Get the refresh token string, if any
+    public var refreshToken: String? {
+        guard let cString = schlussel_token_get_refresh_token(handle) else {
+            return nil
+        }
+        defer { schlussel_string_free(cString) }
+        return String(cString: cString)
+    }
+
+    /// Get the granted scopes (space-separated), if any
+    public var scope: String? {
+        guard let cString = schlussel_token_get_scope(handle) else {
+            return nil
+        }
+        defer { schlussel_string_free(cString) }
+        return String(cString: cString)
+    }
+
+    /// Get the expiration date, or nil if the token doesn't expire
+    public var expiresAt: Date? {
+        let timestamp = schlussel_token_get_expires_at(handle)
+        return timestamp < 0 ? nil : Date(timeIntervalSince1970: TimeInterval(timestamp))
+    }
+
     /// Check if the token is expired
     public var isExpired: Bool {
         return schlussel_token_is_expired(handle) != 0
     }
+
+    /// Check if the token is expired or will expire within `seconds`
+    public func isExpired(within seconds: UInt64) -> Bool {
+        return schlussel_token_is_expired_within(handle, seconds) != 0
+    }
 }
 
 /// Example Usage: